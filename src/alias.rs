@@ -0,0 +1,274 @@
+//! Resolving import specifiers through wildcard alias tables, in the manner of
+//! the `paths` option of a `tsconfig.json`.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str::FromStr;
+
+use crate::{filesystem::FileSystem, path::Path};
+
+/// A table of aliases, each mapping a pattern to a list of target paths.
+///
+/// A pattern is either exact, or holds one `*` which matches any text,
+/// including separators. Either separator can be used, and a trailing one is
+/// ignored. The `*` in a target is replaced by that text, and targets are
+/// joined onto the resolver's base and resolved lexically. An exact pattern is
+/// preferred over a wildcard one, then the wildcard pattern with the longest
+/// text before its `*`.
+#[derive(Debug, Clone)]
+pub struct AliasResolver {
+    base: Path,
+    aliases: Vec<Alias>,
+}
+
+#[derive(Debug, Clone)]
+struct Alias {
+    pattern: String,
+    star: Option<usize>,
+    targets: Vec<String>,
+}
+
+impl AliasResolver {
+    /// Creates a resolver whose targets are relative to `base`.
+    pub fn new(base: impl AsRef<Path>) -> Self {
+        let mut base = base.as_ref().clone();
+        base.is_dir = true;
+
+        AliasResolver {
+            base,
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Adds an alias from `pattern` to `targets`, which are tried in order.
+    pub fn with_alias<'a>(
+        mut self,
+        pattern: &str,
+        targets: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, &'static str> {
+        let pattern = normalize(pattern);
+        let star = find_star(&pattern).ok_or("alias patterns can have at most one `*`")?;
+
+        let targets: Vec<String> = targets.into_iter().map(normalize).collect();
+
+        if targets.is_empty() {
+            return Err("aliases need at least one target");
+        }
+
+        for target in &targets {
+            find_star(target).ok_or("alias targets can have at most one `*`")?;
+            Path::from_str(&target.replace('*', "_"))?;
+        }
+
+        self.aliases.push(Alias {
+            pattern,
+            star,
+            targets,
+        });
+
+        Ok(self)
+    }
+
+    /// Returns the paths `specifier` could refer to, most preferred first, or
+    /// nothing if no alias matches it. Only the most specific matching alias
+    /// is used.
+    pub fn candidates(&self, specifier: impl AsRef<Path>) -> Vec<Path> {
+        let specifier = normalize(
+            &specifier
+                .as_ref()
+                .clone()
+                .builder()
+                .with_separator('/')
+                .build_string(),
+        );
+
+        let Some((alias, capture)) = self.best_match(&specifier) else {
+            return Vec::new();
+        };
+
+        alias
+            .targets
+            .iter()
+            .filter_map(|target| Path::from_str(&target.replacen('*', capture, 1)).ok())
+            .filter_map(|target| self.base.join(target).ok())
+            .map(Path::resolve_lexically)
+            .collect()
+    }
+
+    /// Returns the first candidate for `specifier` which exists in `fs`.
+    pub fn resolve_in(&self, specifier: impl AsRef<Path>, fs: &impl FileSystem) -> Option<Path> {
+        self.candidates(specifier)
+            .into_iter()
+            .find(|candidate| fs.metadata(candidate).is_ok())
+    }
+
+    /// Returns the first candidate for `specifier` which exists on the host
+    /// filesystem.
+    #[cfg(feature = "std")]
+    pub fn resolve(&self, specifier: impl AsRef<Path>) -> Option<Path> {
+        self.resolve_in(specifier, &crate::filesystem::StdFs)
+    }
+
+    /// Returns the most specific alias matching `specifier`, and the text its
+    /// `*` matched.
+    fn best_match<'s>(&self, specifier: &'s str) -> Option<(&Alias, &'s str)> {
+        if let Some(alias) = self
+            .aliases
+            .iter()
+            .find(|alias| alias.star.is_none() && alias.pattern == specifier)
+        {
+            return Some((alias, ""));
+        }
+
+        let mut best: Option<(&Alias, &str, usize)> = None;
+
+        for alias in &self.aliases {
+            let Some(star) = alias.star else {
+                continue;
+            };
+
+            let (prefix, suffix) = (&alias.pattern[..star], &alias.pattern[star + 1..]);
+
+            if specifier.len() < prefix.len() + suffix.len()
+                || !specifier.starts_with(prefix)
+                || !specifier.ends_with(suffix)
+            {
+                continue;
+            }
+
+            if best.is_none_or(|(_, _, len)| prefix.len() > len) {
+                let capture = &specifier[prefix.len()..specifier.len() - suffix.len()];
+                best = Some((alias, capture, prefix.len()));
+            }
+        }
+
+        best.map(|(alias, capture, _)| (alias, capture))
+    }
+}
+
+/// Writes `s` with `/` separators and without a trailing one.
+fn normalize(s: &str) -> String {
+    let s = s.replace('\\', "/");
+
+    match s.strip_suffix('/') {
+        Some(trimmed) if !trimmed.is_empty() => trimmed.to_string(),
+        _ => s,
+    }
+}
+
+/// Returns the index of the `*` in `s` if it has one, or [`None`] inside the
+/// result if it has more.
+fn find_star(s: &str) -> Option<Option<usize>> {
+    let mut stars = s.match_indices('*').map(|(index, _)| index);
+    let first = stars.next();
+
+    stars.next().is_none().then_some(first)
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::filesystem::MemoryFs;
+
+    fn resolver() -> AliasResolver {
+        AliasResolver::new(Path::from_str("/app").unwrap())
+            .with_alias("@/*", ["src/*"])
+            .and_then(|r| r.with_alias("@/components/*", ["src/ui/*", "vendor/ui/*"]))
+            .and_then(|r| r.with_alias("~lib/*", ["/opt/lib/*.ts"]))
+            .and_then(|r| r.with_alias("config", ["config/default.ts"]))
+            .and_then(|r| r.with_alias("*", ["types/*"]))
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case("@/util/date", vec!["/app/src/util/date"])]
+    #[case("@/components/Button", vec!["/app/src/ui/Button", "/app/vendor/ui/Button"])]
+    #[case("~lib/fmt", vec!["/opt/lib/fmt.ts"])]
+    #[case("config", vec!["/app/config/default.ts"])]
+    #[case("react", vec!["/app/types/react"])]
+    #[case("@\\components\\Button", vec!["/app/src/ui/Button", "/app/vendor/ui/Button"])]
+    #[case("@/components/", vec!["/app/src/components"])]
+    fn candidates(#[case] specifier: &str, #[case] expected: Vec<&str>) {
+        // act
+        let candidates = resolver().candidates(Path::from_str(specifier).unwrap());
+
+        // assert
+        assert_eq!(
+            candidates,
+            expected
+                .into_iter()
+                .map(|e| Path::from_str(e).unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn no_match() {
+        // arrange
+        let resolver = AliasResolver::new(Path::from_str("/app").unwrap())
+            .with_alias("@/*", ["src/*"])
+            .unwrap();
+
+        // act
+        let candidates = resolver.candidates(Path::from_str("lodash").unwrap());
+
+        // assert
+        assert_eq!(candidates, Vec::<Path>::new());
+    }
+
+    #[rstest]
+    #[case("./src/*", "@/x", "/app/src/x")]
+    #[case("src\\*\\", "@/x", "/app/src/x")]
+    #[case("../shared/*", "@/x/y", "/shared/x/y")]
+    #[case("./src/*", "@\\x\\", "/app/src/x")]
+    fn candidates_normalized(
+        #[case] target: &str,
+        #[case] specifier: &str,
+        #[case] expected: &str,
+    ) {
+        // arrange
+        let resolver = AliasResolver::new(Path::from_str("/app").unwrap())
+            .with_alias("@\\*", [target])
+            .unwrap();
+
+        // act
+        let candidates = resolver.candidates(Path::from_str(specifier).unwrap());
+
+        // assert
+        assert_eq!(candidates, vec![Path::from_str(expected).unwrap()]);
+    }
+
+    #[rstest]
+    #[case("@/components/Button", Some("/app/vendor/ui/Button"))]
+    #[case("@/components/Missing", None)]
+    fn resolve_in(#[case] specifier: &str, #[case] expected: Option<&str>) {
+        // arrange
+        let fs = MemoryFs::new()
+            .with_file(Path::from_str("/app/vendor/ui/Button").unwrap(), 0)
+            .unwrap();
+
+        // act
+        let resolved = resolver().resolve_in(Path::from_str(specifier).unwrap(), &fs);
+
+        // assert
+        assert_eq!(resolved, expected.map(|e| Path::from_str(e).unwrap()));
+    }
+
+    #[rstest]
+    #[case("@/*/*", vec!["src/*"], "alias patterns can have at most one `*`")]
+    #[case("@/*", vec!["src/*/*"], "alias targets can have at most one `*`")]
+    #[case("@/*", vec![], "aliases need at least one target")]
+    fn invalid(#[case] pattern: &str, #[case] targets: Vec<&str>, #[case] expected: &str) {
+        // act
+        let result =
+            AliasResolver::new(Path::from_str("/app").unwrap()).with_alias(pattern, targets);
+
+        // assert
+        assert_eq!(result.err(), Some(expected));
+    }
+}
@@ -4,11 +4,28 @@ use std::ffi::OsString;
 
 use alloc::string::String;
 
-use crate::path::{Drive, Path, Prefix, Root};
+#[cfg(not(feature = "std"))]
+use crate::env::MemoryEnv;
+#[cfg(feature = "std")]
+use crate::env::ProcessEnv;
+use crate::{
+    env::PathEnvironment,
+    msys::MsysConfig,
+    path::{Drive, Path, Prefix, Resolution, Root},
+};
+
+/// The environment paths are resolved against unless another one is given,
+/// the running process when `std` is available and an empty one otherwise.
+#[cfg(feature = "std")]
+pub type DefaultEnv = ProcessEnv;
+#[cfg(not(feature = "std"))]
+pub type DefaultEnv = MemoryEnv;
 
-pub struct StringPathBuilder<T> {
+pub struct StringPathBuilder<T, E = DefaultEnv> {
     path: Path,
     separator: char,
+    env: E,
+    resolution: Resolution,
     _phantom_data: PhantomData<T>,
 }
 
@@ -26,6 +43,22 @@ impl StringPathBuilder<Base> {
         StringPathBuilder::<Base> {
             path: path.into(),
             separator: '/',
+            env: DefaultEnv::default(),
+            resolution: Resolution::default(),
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<E: PathEnvironment> StringPathBuilder<Base, E> {
+    /// Resolves the current directory, home directories and users through
+    /// `env` instead of the [`DefaultEnv`].
+    pub fn with_environment<E2: PathEnvironment>(self, env: E2) -> StringPathBuilder<Base, E2> {
+        StringPathBuilder {
+            path: self.path,
+            separator: self.separator,
+            resolution: self.resolution,
+            env,
             _phantom_data: PhantomData,
         }
     }
@@ -36,7 +69,7 @@ impl StringPathBuilder<Base> {
     }
 
     pub fn with_cwd_base(mut self) -> Result<Self, &'static str> {
-        self.path = self.path.with_cwd_base()?;
+        self.path = self.path.with_cwd_base_in(&self.env)?;
         Ok(self)
     }
 
@@ -45,18 +78,36 @@ impl StringPathBuilder<Base> {
         self
     }
 
-    pub fn with_resolver(self) -> StringPathBuilder<WithResolver> {
-        StringPathBuilder::<WithResolver> {
+    /// Renders the path the way an MSYS or Cygwin shell spells it, translating
+    /// drive paths with [`Path::windows_to_msys`] and separating with `/`.
+    pub fn with_msys_target(mut self, config: &MsysConfig) -> Result<Self, &'static str> {
+        self.path = self.path.windows_to_msys(config)?;
+        self.separator = '/';
+        Ok(self)
+    }
+
+    pub fn with_resolver(self) -> StringPathBuilder<WithResolver, E> {
+        StringPathBuilder {
             path: self.path,
             separator: self.separator,
+            resolution: self.resolution,
+            env: self.env,
             _phantom_data: PhantomData,
         }
     }
 
-    pub fn traverse_symlinks(self) -> StringPathBuilder<WithSymlinkTraversal> {
-        StringPathBuilder::<WithSymlinkTraversal> {
+    /// Resolves the path with the given [`Resolution`] when it's built.
+    pub fn with_resolution(mut self, resolution: Resolution) -> StringPathBuilder<WithResolver, E> {
+        self.resolution = resolution;
+        self.with_resolver()
+    }
+
+    pub fn traverse_symlinks(self) -> StringPathBuilder<WithSymlinkTraversal, E> {
+        StringPathBuilder {
             path: self.path,
             separator: self.separator,
+            resolution: self.resolution,
+            env: self.env,
             _phantom_data: PhantomData,
         }
     }
@@ -76,11 +127,13 @@ impl StringPathBuilder<Base> {
     }
 }
 
-impl StringPathBuilder<WithResolver> {
-    pub fn traverse_symlinks(self) -> StringPathBuilder<WithResolverAndSymlinkTraversal> {
-        StringPathBuilder::<WithResolverAndSymlinkTraversal> {
+impl<E: PathEnvironment> StringPathBuilder<WithResolver, E> {
+    pub fn traverse_symlinks(self) -> StringPathBuilder<WithResolverAndSymlinkTraversal, E> {
+        StringPathBuilder {
             path: self.path,
             separator: self.separator,
+            resolution: self.resolution,
+            env: self.env,
             _phantom_data: PhantomData,
         }
     }
@@ -91,43 +144,66 @@ impl StringPathBuilder<WithResolver> {
     }
 
     pub fn with_cwd_base(mut self) -> Result<Self, &'static str> {
-        self.path = self.path.with_cwd_base()?;
+        self.path = self.path.with_cwd_base_in(&self.env)?;
         Ok(self)
     }
 
     pub fn build_string(mut self) -> Result<String, &'static str> {
-        self.path = self.path.resolve()?;
+        #[cfg(feature = "std")]
+        {
+            self.path = self.path.resolve_with(&self.env, self.resolution)?;
+        }
+
+        // Without std there's no home directory to expand `~` to.
+        #[cfg(not(feature = "std"))]
+        {
+            self.path = self
+                .path
+                .resolve_keeping_tilde(&self.env, self.resolution)?;
+        }
+
         Ok(build_path(self))
     }
 
     #[cfg(feature = "std")]
     pub fn build_os_string(self) -> Result<OsString, &'static str> {
-        self.build_string().map(|s| OsString::from(s))
+        self.build_string().map(OsString::from)
     }
 
     #[cfg(feature = "std")]
     pub fn build_std_path(self) -> Result<std::path::PathBuf, &'static str> {
-        self.build_string().map(|s| std::path::PathBuf::from(s))
+        self.build_string().map(std::path::PathBuf::from)
     }
 }
 
 #[cfg(feature = "std")]
-impl StringPathBuilder<WithSymlinkTraversal> {
-    pub fn with_resolver(self) -> StringPathBuilder<WithResolverAndSymlinkTraversal> {
-        StringPathBuilder::<WithResolverAndSymlinkTraversal> {
+impl<E: PathEnvironment> StringPathBuilder<WithSymlinkTraversal, E> {
+    pub fn with_resolver(self) -> StringPathBuilder<WithResolverAndSymlinkTraversal, E> {
+        StringPathBuilder {
             path: self.path,
             separator: self.separator,
+            resolution: self.resolution,
+            env: self.env,
             _phantom_data: PhantomData,
         }
     }
 
+    /// Resolves the path with the given [`Resolution`] when it's built.
+    pub fn with_resolution(
+        mut self,
+        resolution: Resolution,
+    ) -> StringPathBuilder<WithResolverAndSymlinkTraversal, E> {
+        self.resolution = resolution;
+        self.with_resolver()
+    }
+
     pub fn with_base(mut self, base: impl AsRef<Path>) -> Result<Self, &'static str> {
         self.path = self.path.join(base)?;
         Ok(self)
     }
 
     pub fn with_cwd_base(mut self) -> Result<Self, &'static str> {
-        self.path = self.path.with_cwd_base()?;
+        self.path = self.path.with_cwd_base_in(&self.env)?;
         Ok(self)
     }
 
@@ -137,18 +213,21 @@ impl StringPathBuilder<WithSymlinkTraversal> {
     }
 
     pub fn build_os_string(self) -> Result<OsString, &'static str> {
-        self.build_string().map(|s| OsString::from(s))
+        self.build_string().map(OsString::from)
     }
 
     pub fn build_std_path(self) -> Result<std::path::PathBuf, &'static str> {
-        self.build_string().map(|s| std::path::PathBuf::from(s))
+        self.build_string().map(std::path::PathBuf::from)
     }
 }
 
 #[cfg(feature = "std")]
-impl StringPathBuilder<WithResolverAndSymlinkTraversal> {
+impl<E: PathEnvironment> StringPathBuilder<WithResolverAndSymlinkTraversal, E> {
     pub fn build_string(mut self) -> Result<String, &'static str> {
-        self.path = self.path.resolve()?.traverse_symlinks()?;
+        self.path = self
+            .path
+            .resolve_with(&self.env, self.resolution)?
+            .traverse_symlinks()?;
         Ok(build_path(self))
     }
 
@@ -158,20 +237,20 @@ impl StringPathBuilder<WithResolverAndSymlinkTraversal> {
     }
 
     pub fn with_cwd_base(mut self) -> Result<Self, &'static str> {
-        self.path = self.path.with_cwd_base()?;
+        self.path = self.path.with_cwd_base_in(&self.env)?;
         Ok(self)
     }
 
     pub fn build_os_string(self) -> Result<OsString, &'static str> {
-        self.build_string().map(|s| OsString::from(s))
+        self.build_string().map(OsString::from)
     }
 
     pub fn build_std_path(self) -> Result<std::path::PathBuf, &'static str> {
-        self.build_string().map(|s| std::path::PathBuf::from(s))
+        self.build_string().map(std::path::PathBuf::from)
     }
 }
 
-fn build_path<T>(builder: StringPathBuilder<T>) -> String {
+fn build_path<T, E>(builder: StringPathBuilder<T, E>) -> String {
     let mut result = String::new();
 
     match builder.path.prefix {
@@ -196,13 +275,12 @@ fn build_path<T>(builder: StringPathBuilder<T>) -> String {
     }
 
     match builder.path.root {
-        Some(Root::Normal) => {
-            if !builder.path.prefix.is_some_and(|p| p == Prefix::Device)
-                || builder.path.drive.is_some()
-            {
-                result.push(builder.separator);
-            }
+        Some(Root::Normal)
+            if builder.path.prefix != Some(Prefix::Device) || builder.path.drive.is_some() =>
+        {
+            result.push(builder.separator);
         }
+        Some(Root::Normal) => {}
         Some(Root::Unc) => {
             if let Some(Prefix::ExtendedPath) = builder.path.prefix {
                 result.push_str("UNC");
@@ -225,6 +303,16 @@ fn build_path<T>(builder: StringPathBuilder<T>) -> String {
         }
     }
 
+    if let Some(stream) = builder.path.stream {
+        result.push(':');
+        result.push_str(&stream.name);
+
+        if let Some(stream_type) = stream.stream_type {
+            result.push(':');
+            result.push_str(&stream_type);
+        }
+    }
+
     result
 }
 
@@ -234,6 +322,7 @@ mod test {
     use rstest::rstest;
 
     use super::*;
+    use crate::env::MemoryEnv;
 
     #[rstest]
     fn build_with_defaults() {
@@ -261,6 +350,42 @@ mod test {
         assert_eq!(string, r"a\b\c");
     }
 
+    #[rstest]
+    #[case("notes.txt:Zone.Identifier", "notes.txt:Zone.Identifier")]
+    #[case(r"C:\dir\file.txt::$DATA", r"C:\dir\file.txt::$DATA")]
+    #[case(r"\\?\C:\dir\file.txt:s:$DATA", r"\\?\C:\dir\file.txt:s:$DATA")]
+    fn build_with_stream(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let string = path.builder().with_separator('\\').build_string();
+
+        // assert
+        assert_eq!(string, expected);
+    }
+
+    #[rstest]
+    #[case(r"C:\Users\me\file.txt", "/c/Users/me/file.txt")]
+    #[case(r"C:\msys64\usr\bin", "/usr/bin")]
+    #[case(r"\\server\share\x", "//server/share/x")]
+    #[case(r"a\b", "a/b")]
+    fn build_with_msys_target(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let string = path
+            .builder()
+            .with_separator('\\')
+            .with_msys_target(&MsysConfig::msys2())
+            .unwrap()
+            .build_string();
+
+        // assert
+        assert_eq!(string, expected);
+    }
+
     #[rstest]
     fn build_with_resolver() {
         // arrange
@@ -425,4 +550,72 @@ mod test {
         // assert
         assert_eq!(resolved, path);
     }
+
+    #[rstest]
+    #[case("src/../Cargo.toml", "/work/canonic/Cargo.toml")]
+    #[case("./notes/", "/work/canonic/notes")]
+    fn build_with_environment(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let env = MemoryEnv::new()
+            .with_current_dir(Path::from_str("/work/canonic").unwrap())
+            .with_home_dir(Path::from_str("/home/me").unwrap())
+            .with_user("alice", Path::from_str("/home/alice").unwrap());
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let string = path
+            .builder()
+            .with_environment(&env)
+            .with_cwd_base()
+            .unwrap()
+            .with_resolver()
+            .build_string()
+            .unwrap();
+
+        // assert
+        assert_eq!(string, expected);
+    }
+
+    #[rstest]
+    #[case("~/notes", "/home/me/notes")]
+    #[case("~alice/notes", "/home/alice/notes")]
+    #[case("~bob/notes", "~bob/notes")]
+    fn build_with_environment_resolver(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let env = MemoryEnv::new()
+            .with_home_dir(Path::from_str("/home/me").unwrap())
+            .with_user("alice", Path::from_str("/home/alice").unwrap());
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let string = path
+            .builder()
+            .with_environment(&env)
+            .with_resolver()
+            .build_string()
+            .unwrap();
+
+        // assert
+        assert_eq!(string, expected);
+    }
+
+    #[rstest]
+    #[case(Resolution::Lexical, "../b")]
+    #[case(Resolution::Logical, "/work/b")]
+    fn build_with_resolution(#[case] resolution: Resolution, #[case] expected: &str) {
+        // arrange
+        let env = MemoryEnv::new().with_current_dir(Path::from_str("/work/app").unwrap());
+        let path = Path::from_str("../a/../b").unwrap();
+
+        // act
+        let string = path
+            .builder()
+            .with_environment(&env)
+            .with_resolution(resolution)
+            .build_string()
+            .unwrap();
+
+        // assert
+        assert_eq!(string, expected);
+    }
 }
@@ -0,0 +1,362 @@
+//! Prefix and suffix queries which compare paths by whole segments.
+
+use crate::{
+    path::{Path, Root, is_anchored},
+    pattern::{CaseSensitivity, raw_segments},
+};
+
+/// Decides whether two path segments are equal.
+///
+/// Implemented for [`CaseSensitivity`] and for closures taking both segments.
+pub trait SegmentComparator {
+    fn eq(&self, l: &str, r: &str) -> bool;
+}
+
+impl SegmentComparator for CaseSensitivity {
+    fn eq(&self, l: &str, r: &str) -> bool {
+        match self {
+            CaseSensitivity::Sensitive => l == r,
+            CaseSensitivity::Insensitive => l
+                .chars()
+                .flat_map(char::to_lowercase)
+                .eq(r.chars().flat_map(char::to_lowercase)),
+        }
+    }
+}
+
+impl<F: Fn(&str, &str) -> bool> SegmentComparator for F {
+    fn eq(&self, l: &str, r: &str) -> bool {
+        self(l, r)
+    }
+}
+
+impl Path {
+    /// Returns whether `base` is a prefix of this path: it has the same
+    /// prefix, drive and root, and its segments start this path's segments.
+    ///
+    /// Paths are compared lexically, so they should usually be resolved first.
+    pub fn starts_with(&self, base: impl AsRef<Path>) -> bool {
+        self.starts_with_by(base, CaseSensitivity::Sensitive)
+    }
+
+    /// [`Path::starts_with`], comparing segments with `comparator`.
+    pub fn starts_with_by(
+        &self,
+        base: impl AsRef<Path>,
+        comparator: impl SegmentComparator,
+    ) -> bool {
+        self.prefix_len(base.as_ref(), &comparator).is_some()
+    }
+
+    /// Returns whether this path's segments end with the segments of `child`.
+    /// An anchored `child` only matches a path equal to it.
+    pub fn ends_with(&self, child: impl AsRef<Path>) -> bool {
+        self.ends_with_by(child, CaseSensitivity::Sensitive)
+    }
+
+    /// [`Path::ends_with`], comparing segments with `comparator`.
+    pub fn ends_with_by(
+        &self,
+        child: impl AsRef<Path>,
+        comparator: impl SegmentComparator,
+    ) -> bool {
+        let child = child.as_ref();
+
+        if is_anchored(&child.prefix, &child.drive, &child.root) {
+            return self
+                .prefix_len(child, &comparator)
+                .is_some_and(|len| len == self.segments.len());
+        }
+
+        let segments = raw_segments(self);
+        let child_segments = raw_segments(child);
+
+        child_segments.len() <= segments.len()
+            && segments
+                .iter()
+                .rev()
+                .zip(child_segments.iter().rev())
+                .all(|(l, r)| comparator.eq(l, r))
+    }
+
+    /// Returns this path relative to `base`, if `base` is a prefix of it.
+    pub fn strip_prefix(&self, base: impl AsRef<Path>) -> Option<Path> {
+        self.strip_prefix_by(base, CaseSensitivity::Sensitive)
+    }
+
+    /// [`Path::strip_prefix`], comparing segments with `comparator`.
+    pub fn strip_prefix_by(
+        &self,
+        base: impl AsRef<Path>,
+        comparator: impl SegmentComparator,
+    ) -> Option<Path> {
+        let base = base.as_ref();
+        let len = self.prefix_len(base, &comparator)?;
+
+        let mut rest = Path::new();
+
+        for segment in self.segments.iter().skip(len) {
+            rest.segments.push(segment.0.clone());
+        }
+
+        if base.stream.is_none() {
+            rest.stream = self.stream.clone();
+        }
+
+        rest.is_dir = self.is_dir;
+        Some(rest)
+    }
+
+    /// Moves this path from beneath `from` to the same place beneath `to`, if
+    /// `from` is a prefix of it.
+    pub fn rebase(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Option<Path> {
+        self.rebase_by(from, to, CaseSensitivity::Sensitive)
+    }
+
+    /// [`Path::rebase`], comparing segments with `comparator`.
+    pub fn rebase_by(
+        &self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+        comparator: impl SegmentComparator,
+    ) -> Option<Path> {
+        let rest = self.strip_prefix_by(from, comparator)?;
+        let mut rebased = to.as_ref().clone();
+
+        for segment in rest.segments.iter() {
+            rebased.segments.push(segment.0.clone());
+        }
+
+        rebased.stream = rest.stream;
+        rebased.is_dir = rest.is_dir;
+        Some(rebased)
+    }
+
+    /// Returns the number of segments of `base` if it's a prefix of this path.
+    fn prefix_len(&self, base: &Path, comparator: &impl SegmentComparator) -> Option<usize> {
+        let same_drive = match (&self.drive, &base.drive) {
+            (Some(l), Some(r)) => l.letter.eq_ignore_ascii_case(&r.letter),
+            (None, None) => true,
+            _ => false,
+        };
+
+        if self.prefix != base.prefix || self.root != base.root || !same_drive {
+            return None;
+        }
+
+        let segments = raw_segments(self);
+        let base_segments = raw_segments(base);
+
+        if base_segments.len() > segments.len() {
+            return None;
+        }
+
+        // Like drive letters, UNC server and share names ignore case.
+        let fixed = if self.root == Some(Root::Unc) { 2 } else { 0 };
+
+        segments
+            .iter()
+            .zip(&base_segments)
+            .enumerate()
+            .all(|(i, (l, r))| {
+                if i < fixed {
+                    l.eq_ignore_ascii_case(r)
+                } else {
+                    comparator.eq(l, r)
+                }
+            })
+            .then_some(base_segments.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("", "", true)]
+    #[case("file", "", true)]
+    #[case("file", "file", true)]
+    #[case("dir/file", "dir", true)]
+    #[case("dir/file", "dir/", true)]
+    #[case("dir/file", "/dir", false)]
+    #[case("/", "/", true)]
+    #[case("/foo/barbaz", "/foo/bar", false)]
+    #[case("/foo/bar/baz", "/foo/bar", true)]
+    #[case("/foo/bar", "/foo/bar/baz", false)]
+    #[case("/foo/bar", "/", true)]
+    #[case("/foo/bar", "", false)]
+    #[case("C:", "C:", true)]
+    #[case("C:/Users/me", "C:/Users", true)]
+    #[case("C:/Users/me", "c:/Users", true)]
+    #[case("C:/Users/me", "D:/Users", false)]
+    #[case("C:Users/me", "C:/Users", false)]
+    #[case("C:Users/me", "C:Users", true)]
+    #[case(r"\\Server\Share\foo", r"\\Server\Share", true)]
+    #[case(r"\\Server\Share\foo", r"\\Server\Other", false)]
+    #[case(r"\\server\share\x", r"\\Server\Share", true)]
+    #[case(r"\\server\share\x", r"\\Server\Share\X", false)]
+    #[case(r"\\?\UNC\server\share\x", r"\\?\UNC\SERVER\share", true)]
+    #[case(r"\\Server\Share\foo", "/Server/Share", false)]
+    #[case(r"\\?\C:\foo\bar", r"\\?\C:\foo", true)]
+    #[case(r"\\?\C:\foo\bar", r"C:\foo", false)]
+    #[case(r"\\.\COM1", r"\\.\", true)]
+    #[case("//?/UNC/server/share/file", "//?/UNC/server/share", true)]
+    #[case("~/file.txt", "~", true)]
+    #[case("~/file.txt", "/", false)]
+    #[case("C:/a/file.txt:stream", "C:/a", true)]
+    #[case("C:/a/file.txt:stream", "C:/a/file.txt", false)]
+    #[case("/a/b:c", "/a/b", false)]
+    #[case("C:/a/file.txt:stream", "C:/a/file.txt:stream", true)]
+    fn starts_with(#[case] path: &str, #[case] base: &str, #[case] expected: bool) {
+        // act
+        let result = Path::from_str(path)
+            .unwrap()
+            .starts_with(Path::from_str(base).unwrap());
+
+        // assert
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case("/foo/bar/baz", "baz", true)]
+    #[case("/foo/bar/baz", "bar/baz", true)]
+    #[case("/foo/bar/baz", "r/baz", false)]
+    #[case("/foo/bar/baz", "/foo/bar/baz", true)]
+    #[case("/foo/bar/baz", "/bar/baz", false)]
+    #[case("foo/bar", "foo/bar", true)]
+    #[case("bar", "foo/bar", false)]
+    #[case("C:/Users/me", "Users/me", true)]
+    #[case("C:/Users/me", "c:/Users/me", true)]
+    #[case(r"\\Server\Share\foo", "foo", true)]
+    #[case(r"\\?\C:\foo\bar", "foo/bar", true)]
+    #[case(r"\\.\COM1", "COM1", true)]
+    #[case("~/file.txt", "file.txt", true)]
+    #[case("C:/a/file.txt:stream", "file.txt:stream", true)]
+    #[case("C:/a/file.txt:stream", "file.txt", false)]
+    #[case("/a/b/", "b", true)]
+    fn ends_with(#[case] path: &str, #[case] child: &str, #[case] expected: bool) {
+        // act
+        let result = Path::from_str(path)
+            .unwrap()
+            .ends_with(Path::from_str(child).unwrap());
+
+        // assert
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case("/foo/bar/baz", "/foo", Some("bar/baz"))]
+    #[case("/foo/bar/baz/", "/foo", Some("bar/baz/"))]
+    #[case("/foo/barbaz", "/foo/bar", None)]
+    #[case("/foo", "/foo", Some(""))]
+    #[case("dir/file", "dir", Some("file"))]
+    #[case("C:/Users/me", "C:/", Some("Users/me"))]
+    #[case("C:Users/me", "C:", Some("Users/me"))]
+    #[case(r"\\Server\Share\foo\bar", r"\\Server\Share", Some("foo/bar"))]
+    #[case(r"\\?\C:\foo\bar", r"\\?\C:\", Some("foo/bar"))]
+    #[case(r"\\.\COM1", r"\\.\", Some("COM1"))]
+    #[case("~/dir/file.txt", "~", Some("dir/file.txt"))]
+    #[case("C:/a/file.txt:stream", "C:/a/file.txt:stream", Some(""))]
+    fn strip_prefix(#[case] path: &str, #[case] base: &str, #[case] expected: Option<&str>) {
+        // act
+        let result = Path::from_str(path)
+            .unwrap()
+            .strip_prefix(Path::from_str(base).unwrap());
+
+        // assert
+        assert_eq!(result, expected.map(|e| Path::from_str(e).unwrap()));
+    }
+
+    #[rstest]
+    fn strip_prefix_keeps_stream() {
+        // act
+        let result = Path::from_str("C:/a/file.txt:stream")
+            .unwrap()
+            .strip_prefix(Path::from_str("C:/a").unwrap())
+            .unwrap();
+
+        // assert
+        assert_eq!(result.basename(), Some("file.txt"));
+        assert_eq!(result.stream().map(|s| s.name()), Some("stream"));
+    }
+
+    #[rstest]
+    #[case(
+        "/home/ci/build/src/lib.rs",
+        "/home/ci/build",
+        "/src",
+        Some("/src/src/lib.rs")
+    )]
+    #[case("/home/ci/build", "/home/ci/build", "/src", Some("/src"))]
+    #[case("/home/ci/builds/x", "/home/ci/build", "/src", None)]
+    #[case("C:/work/a/", "C:/work", "/mnt/c/work", Some("/mnt/c/work/a/"))]
+    #[case(
+        r"\\Server\Share\a.txt:s",
+        r"\\Server\Share",
+        r"D:\share",
+        Some(r"D:\share\a.txt:s")
+    )]
+    #[case("src/a.rs", "src", "lib", Some("lib/a.rs"))]
+    fn rebase(
+        #[case] path: &str,
+        #[case] from: &str,
+        #[case] to: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        // act
+        let result = Path::from_str(path)
+            .unwrap()
+            .rebase(Path::from_str(from).unwrap(), Path::from_str(to).unwrap());
+
+        // assert
+        assert_eq!(result, expected.map(|e| Path::from_str(e).unwrap()));
+    }
+
+    #[rstest]
+    #[case(CaseSensitivity::Sensitive, "/Users/Me/file", "/users/me", false)]
+    #[case(CaseSensitivity::Insensitive, "/Users/Me/file", "/users/me", true)]
+    #[case(CaseSensitivity::Insensitive, "/Users/Me/file", "/users/you", false)]
+    #[case(CaseSensitivity::Insensitive, "/ÄRGER/file", "/ärger", true)]
+    #[case(CaseSensitivity::Insensitive, "/Users/Me/file", "/users/m", false)]
+    fn starts_with_by(
+        #[case] comparator: CaseSensitivity,
+        #[case] path: &str,
+        #[case] base: &str,
+        #[case] expected: bool,
+    ) {
+        // act
+        let result = Path::from_str(path)
+            .unwrap()
+            .starts_with_by(Path::from_str(base).unwrap(), comparator);
+
+        // assert
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    fn comparator_variants() {
+        // arrange
+        let path = Path::from_str("C:/Users/Me/Docs/a.txt").unwrap();
+        let insensitive = |l: &str, r: &str| l.eq_ignore_ascii_case(r);
+
+        // act & assert
+        assert!(path.ends_with_by(Path::from_str("docs/A.TXT").unwrap(), insensitive));
+        assert_eq!(
+            path.strip_prefix_by(Path::from_str("c:/users/me").unwrap(), insensitive),
+            Some(Path::from_str("Docs/a.txt").unwrap())
+        );
+        assert_eq!(
+            path.rebase_by(
+                Path::from_str("C:/USERS/ME").unwrap(),
+                Path::from_str("/home/me").unwrap(),
+                CaseSensitivity::Insensitive
+            ),
+            Some(Path::from_str("/home/me/Docs/a.txt").unwrap())
+        );
+    }
+}
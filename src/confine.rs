@@ -0,0 +1,279 @@
+use core::fmt;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    packed_list::PathSegmentList,
+    path::{Path, Root},
+    windows::dos_device,
+};
+
+/// What [`Path::join_within_with`] does with the parts of an untrusted path
+/// which would escape the base.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EscapePolicy {
+    /// Fail with the first reason the path would escape.
+    #[default]
+    Reject,
+    /// Drop the parts which would escape: prefixes, drives and roots are
+    /// removed, a leading `~` segment is removed, `..` stops at the base and
+    /// segments Windows would rename are removed.
+    Clamp,
+}
+
+/// The reason an untrusted path was rejected by [`Path::join_within`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EscapeError {
+    /// The path is absolute, as in `/etc/passwd` or `\Windows`.
+    Absolute,
+    /// The path names a drive, as in `C:\x` or `C:x`.
+    Drive,
+    /// The path is a UNC path, as in `\\server\share\x`.
+    Unc,
+    /// The path is a `\\?\` or `\\.\` path.
+    Device,
+    /// The path starts in a home directory, as in `~/x` or `~user/x`.
+    Home(String),
+    /// A `..` segment at the given index would rise above the base.
+    ParentTraversal(usize),
+    /// The segment at the given index ends in a dot or space, which Windows
+    /// strips, so `.. ` is `..` there.
+    TrailingDotOrSpace(usize),
+    /// The segment at the given index names a DOS device such as `NUL`, which
+    /// Windows opens wherever it appears.
+    DosDevice(usize),
+    /// The joined path wasn't valid.
+    Path(&'static str),
+}
+
+impl fmt::Display for EscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EscapeError::Absolute => f.write_str("absolute paths cannot be joined within a base"),
+            EscapeError::Drive => f.write_str("paths with a drive cannot be joined within a base"),
+            EscapeError::Unc => f.write_str("unc paths cannot be joined within a base"),
+            EscapeError::Device => f.write_str("device paths cannot be joined within a base"),
+            EscapeError::Home(segment) => {
+                write!(
+                    f,
+                    "{segment}: home directories cannot be joined within a base"
+                )
+            }
+            EscapeError::ParentTraversal(index) => {
+                write!(f, "segment {index}: `..` would rise above the base")
+            }
+            EscapeError::TrailingDotOrSpace(index) => {
+                write!(f, "segment {index}: trailing dots and spaces are ambiguous")
+            }
+            EscapeError::DosDevice(index) => {
+                write!(f, "segment {index}: DOS device names cannot be joined")
+            }
+            EscapeError::Path(message) => f.write_str(message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EscapeError {}
+
+impl Path {
+    /// Joins an untrusted relative path onto this one, failing if the result
+    /// could end up anywhere but inside `self`.
+    ///
+    /// This is [`Path::join_within_with`] with [`EscapePolicy::Reject`].
+    pub fn join_within(&self, untrusted: impl AsRef<Path>) -> Result<Self, EscapeError> {
+        self.join_within_with(untrusted, EscapePolicy::Reject)
+    }
+
+    /// Joins an untrusted relative path onto this one, so that the result always
+    /// lies inside `self`.
+    ///
+    /// Prefixed, UNC, drive and rooted paths, a leading `~`, any `..` which
+    /// rises above `self` after `.` and `..` are resolved, and segments which
+    /// Windows would rename or open as a device are handled according to
+    /// `policy`. The check is purely lexical, so symlinks inside `self` are not
+    /// accounted for.
+    pub fn join_within_with(
+        &self,
+        untrusted: impl AsRef<Path>,
+        policy: EscapePolicy,
+    ) -> Result<Self, EscapeError> {
+        let untrusted = untrusted.as_ref();
+        let reject = policy == EscapePolicy::Reject;
+
+        let reason = if untrusted.prefix.is_some() {
+            Some(EscapeError::Device)
+        } else if untrusted.root == Some(Root::Unc) {
+            Some(EscapeError::Unc)
+        } else if untrusted.drive.is_some() {
+            Some(EscapeError::Drive)
+        } else if untrusted.root.is_some() {
+            Some(EscapeError::Absolute)
+        } else {
+            None
+        };
+
+        if reject && let Some(reason) = reason {
+            return Err(reason);
+        }
+
+        let mut segments = untrusted.segments.iter().enumerate().peekable();
+
+        if let Some((_, head)) = segments.peek()
+            && head.0.starts_with('~')
+        {
+            if reject {
+                return Err(EscapeError::Home(head.0.clone()));
+            }
+
+            segments.next();
+        }
+
+        let mut kept = Vec::new();
+
+        for (index, segment) in segments {
+            match segment.0.as_str() {
+                "." => {}
+                ".." => {
+                    if kept.pop().is_none() && reject {
+                        return Err(EscapeError::ParentTraversal(index));
+                    }
+                }
+                name if name.ends_with(['.', ' ']) => {
+                    if reject {
+                        return Err(EscapeError::TrailingDotOrSpace(index));
+                    }
+                }
+                name if dos_device(name).is_some() => {
+                    if reject {
+                        return Err(EscapeError::DosDevice(index));
+                    }
+                }
+                _ => kept.push(segment.clone()),
+            }
+        }
+
+        if kept.is_empty() {
+            let mut base = self.clone();
+            base.is_dir = true;
+            return Ok(base);
+        }
+
+        let is_dir = untrusted.is_dir;
+        let mut segments = PathSegmentList::new();
+
+        for segment in kept {
+            segments.push(segment);
+        }
+
+        let relative = Path {
+            segments,
+            stream: untrusted.stream.clone(),
+            is_dir,
+            ..Path::new()
+        };
+
+        self.join(relative).map_err(EscapeError::Path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("a/b.txt", "/srv/uploads/a/b.txt")]
+    #[case("a/./b/../c.txt", "/srv/uploads/a/c.txt")]
+    #[case(r"a\b\", "/srv/uploads/a/b/")]
+    #[case("a/../b", "/srv/uploads/b")]
+    #[case("a/..", "/srv/uploads/")]
+    #[case(".", "/srv/uploads/")]
+    #[case("a/~/b", "/srv/uploads/a/~/b")]
+    #[case("notes.txt:Zone.Identifier", "/srv/uploads/notes.txt:Zone.Identifier")]
+    fn join_within(#[case] untrusted: &str, #[case] expected: &str) {
+        // arrange
+        let base = Path::from_str("/srv/uploads").unwrap();
+        let untrusted = Path::from_str(untrusted).unwrap();
+
+        // act
+        let joined = base.join_within(&untrusted).unwrap();
+
+        // assert
+        assert_eq!(joined, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case("/etc/passwd", EscapeError::Absolute)]
+    #[case(r"\Windows\x", EscapeError::Absolute)]
+    #[case(r"C:\Windows\x", EscapeError::Drive)]
+    #[case("C:x", EscapeError::Drive)]
+    #[case(r"\\server\share\x", EscapeError::Unc)]
+    #[case(r"\\?\C:\x", EscapeError::Device)]
+    #[case(r"\\.\COM1", EscapeError::Device)]
+    #[case("~/.ssh/id_rsa", EscapeError::Home("~".into()))]
+    #[case("~root/x", EscapeError::Home("~root".into()))]
+    #[case("../x", EscapeError::ParentTraversal(0))]
+    #[case("a/../../x", EscapeError::ParentTraversal(2))]
+    #[case(r"a\b\..\..\..\x", EscapeError::ParentTraversal(4))]
+    #[case(".. /x", EscapeError::TrailingDotOrSpace(0))]
+    #[case("a/.../x", EscapeError::TrailingDotOrSpace(1))]
+    #[case("a/b. ", EscapeError::TrailingDotOrSpace(1))]
+    #[case("CON", EscapeError::DosDevice(0))]
+    #[case("a/nul.txt", EscapeError::DosDevice(1))]
+    #[case("a/Com1 .log", EscapeError::DosDevice(1))]
+    fn join_within_should_reject(#[case] untrusted: &str, #[case] expected: EscapeError) {
+        // arrange
+        let base = Path::from_str("/srv/uploads").unwrap();
+        let untrusted = Path::from_str(untrusted).unwrap();
+
+        // act
+        let joined = base.join_within(&untrusted);
+
+        // assert
+        assert_eq!(joined, Err(expected));
+    }
+
+    #[rstest]
+    #[case("/etc/passwd", "/srv/uploads/etc/passwd")]
+    #[case(r"C:\Windows\x", "/srv/uploads/Windows/x")]
+    #[case("C:x", "/srv/uploads/x")]
+    #[case(r"\\server\share\x", "/srv/uploads/server/share/x")]
+    #[case(r"\\?\C:\x", "/srv/uploads/x")]
+    #[case("~/.ssh/id_rsa", "/srv/uploads/.ssh/id_rsa")]
+    #[case("../../x", "/srv/uploads/x")]
+    #[case("a/../../b/../../c", "/srv/uploads/c")]
+    #[case("..", "/srv/uploads/")]
+    #[case(".. /.. /x", "/srv/uploads/x")]
+    #[case("a/.../b", "/srv/uploads/a/b")]
+    #[case("a/NUL/b", "/srv/uploads/a/b")]
+    fn join_within_should_clamp(#[case] untrusted: &str, #[case] expected: &str) {
+        // arrange
+        let base = Path::from_str("/srv/uploads").unwrap();
+        let untrusted = Path::from_str(untrusted).unwrap();
+
+        // act
+        let joined = base
+            .join_within_with(&untrusted, EscapePolicy::Clamp)
+            .unwrap();
+
+        // assert
+        assert_eq!(joined, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    fn join_within_windows_base() {
+        // arrange
+        let base = Path::from_str(r"C:\uploads").unwrap();
+        let untrusted = Path::from_str(r"..\x").unwrap();
+
+        // act
+        let joined = base.join_within_with(&untrusted, EscapePolicy::Clamp);
+
+        // assert
+        assert_eq!(joined, Ok(Path::from_str(r"C:\uploads\x").unwrap()));
+    }
+}
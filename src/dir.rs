@@ -0,0 +1,508 @@
+use std::{
+    ffi::CString,
+    fs::File,
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::ffi::OsStrExt,
+    },
+    path::PathBuf,
+};
+
+use alloc::{collections::VecDeque, string::String, vec::Vec};
+
+use crate::{
+    packed_list::PathSegmentList,
+    path::{Path, PathSegment},
+    physical::DEFAULT_SYMLINK_HOPS,
+};
+
+/// How many times a lookup is retried when `openat2(2)` fails with `EAGAIN`
+/// before the path is walked by hand.
+const OPENAT2_RETRIES: usize = 8;
+
+/// How a [`Dir`] treats symlinks beneath it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Any symlink along the path fails the lookup with `ELOOP`.
+    #[default]
+    Deny,
+    /// Symlinks are followed as long as they stay beneath the directory.
+    /// Absolute symlinks and symlinks which climb out fail with `EXDEV`.
+    Beneath,
+}
+
+/// A handle to a directory which paths are resolved beneath, and never outside
+/// of, even when the tree changes while they're being resolved.
+///
+/// Lookups use `openat2(2)` with `RESOLVE_BENEATH` where the kernel supports
+/// it, and otherwise walk the path one segment at a time with `openat(2)`,
+/// following symlinks by hand. Paths which would escape fail with `EXDEV`, and
+/// symlinks the [`SymlinkPolicy`] denies fail with `ELOOP`.
+#[derive(Debug)]
+pub struct Dir {
+    fd: OwnedFd,
+    path: Path,
+    symlinks: SymlinkPolicy,
+}
+
+impl Dir {
+    /// Opens `root` as the directory to resolve paths beneath. `root` itself is
+    /// trusted, so it's canonicalized first.
+    pub fn open(root: impl AsRef<Path>) -> io::Result<Self> {
+        let root = std::fs::canonicalize(PathBuf::from(root.as_ref()))?;
+        let name = CString::new(root.as_os_str().as_bytes())?;
+
+        // SAFETY: `name` is a NUL-terminated string which outlives the call.
+        let fd = unsafe {
+            libc::open(
+                name.as_ptr(),
+                libc::O_PATH | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+            )
+        };
+
+        let mut path = Path::try_from(root).map_err(invalid_input)?;
+        path.is_dir = true;
+
+        Ok(Dir {
+            fd: owned(fd)?,
+            path,
+            symlinks: SymlinkPolicy::default(),
+        })
+    }
+
+    pub fn with_symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// The canonical path of this directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens the file at `path` beneath this directory for reading, returning it
+    /// along with its canonical path.
+    pub fn open_file(&self, path: impl AsRef<Path>) -> io::Result<(File, Path)> {
+        let (fd, path) = self.open_beneath(path.as_ref(), libc::O_RDONLY)?;
+        Ok((File::from(fd), path))
+    }
+
+    /// Opens the directory at `path` beneath this one. The new handle confines
+    /// lookups to itself and has the same symlink policy.
+    pub fn open_dir(&self, path: impl AsRef<Path>) -> io::Result<Dir> {
+        let flags = libc::O_PATH | libc::O_DIRECTORY;
+        let (fd, mut path) = self.open_beneath(path.as_ref(), flags)?;
+        path.is_dir = true;
+
+        Ok(Dir {
+            fd,
+            path,
+            symlinks: self.symlinks,
+        })
+    }
+
+    fn open_beneath(&self, path: &Path, flags: i32) -> io::Result<(OwnedFd, Path)> {
+        let segments = segments(path)?;
+        let flags = flags | libc::O_CLOEXEC;
+
+        let mut retries = 0;
+
+        loop {
+            let error = match openat2(self.fd.as_raw_fd(), &segments, flags, self.symlinks) {
+                Ok(fd) => {
+                    let resolved = match self.symlinks {
+                        SymlinkPolicy::Deny => Some(lexical(&segments)),
+                        SymlinkPolicy::Beneath => self.proc_segments(&fd),
+                    };
+
+                    return match resolved {
+                        Some(resolved) => Ok((fd, self.beneath(resolved, path.is_dir))),
+                        None => self.walk(segments, flags, path.is_dir),
+                    };
+                }
+                Err(e) => e,
+            };
+
+            match error.raw_os_error() {
+                // The tree changed during the lookup, which the kernel won't
+                // resolve racily, so try again and then walk it by hand.
+                Some(libc::EAGAIN) if retries < OPENAT2_RETRIES => retries += 1,
+                Some(libc::EAGAIN | libc::ENOSYS | libc::EPERM) => {
+                    return self.walk(segments, flags, path.is_dir);
+                }
+                // The path escaped or met a symlink the policy denies, which
+                // walking by hand would only find again.
+                Some(libc::EXDEV) => return Err(escape()),
+                Some(libc::ELOOP) => return Err(denied_symlink()),
+                _ => return Err(error),
+            }
+        }
+    }
+
+    /// Finds where an open file ended up through `/proc/self/fd`, which is
+    /// where the kernel keeps the path it was opened at.
+    fn proc_segments(&self, fd: &OwnedFd) -> Option<Vec<String>> {
+        let link = std::fs::read_link(format!("/proc/self/fd/{}", fd.as_raw_fd())).ok()?;
+        let root = PathBuf::from(&self.path);
+        let relative = link.strip_prefix(root).ok()?;
+
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_str().map(String::from))
+            .collect()
+    }
+
+    /// Resolves `segments` one at a time, holding a descriptor to every
+    /// directory on the way so that `..` can't be redirected by a concurrent
+    /// rename.
+    fn walk(&self, segments: Vec<String>, flags: i32, is_dir: bool) -> io::Result<(OwnedFd, Path)> {
+        let mut queue = VecDeque::from(segments);
+        let mut stack: Vec<(OwnedFd, String)> = Vec::new();
+        let mut hops = 0;
+
+        while let Some(segment) = queue.pop_front() {
+            match segment.as_str() {
+                "." => continue,
+                ".." => {
+                    stack.pop().ok_or_else(escape)?;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let parent = stack
+                .last()
+                .map_or(self.fd.as_raw_fd(), |(fd, _)| fd.as_raw_fd());
+            let fd = openat(parent, &segment, libc::O_PATH | libc::O_NOFOLLOW)?;
+
+            if is_symlink(&fd)? {
+                if self.symlinks == SymlinkPolicy::Deny {
+                    return Err(denied_symlink());
+                }
+
+                hops += 1;
+
+                if hops > DEFAULT_SYMLINK_HOPS {
+                    return Err(io::Error::from_raw_os_error(libc::ELOOP));
+                }
+
+                let target = readlink(&fd)?;
+
+                if target.starts_with('/') {
+                    return Err(escape());
+                }
+
+                for segment in target.split('/').filter(|s| !s.is_empty()).rev() {
+                    queue.push_front(String::from(segment));
+                }
+
+                continue;
+            }
+
+            stack.push((fd, segment));
+        }
+
+        let names = stack.iter().map(|(_, name)| name.clone()).collect();
+
+        let fd = match stack.pop() {
+            Some((fd, name)) => {
+                let parent = stack
+                    .last()
+                    .map_or(self.fd.as_raw_fd(), |(fd, _)| fd.as_raw_fd());
+                let opened = openat(parent, &name, flags | libc::O_NOFOLLOW)?;
+
+                if !same_file(&fd, &opened)? {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Interrupted,
+                        "the path changed while it was being opened",
+                    ));
+                }
+
+                opened
+            }
+            None => openat(self.fd.as_raw_fd(), ".", flags)?,
+        };
+
+        Ok((fd, self.beneath(names, is_dir)))
+    }
+
+    fn beneath(&self, segments: Vec<String>, is_dir: bool) -> Path {
+        let mut path = self.path.clone();
+        path.is_dir = is_dir || segments.is_empty();
+
+        for segment in segments {
+            path.segments.push(PathSegment(segment));
+        }
+
+        path
+    }
+}
+
+/// Renders the segments of `path` as file names, failing with `EXDEV` for
+/// paths which start anywhere but the directory they're resolved beneath.
+fn segments(path: &Path) -> io::Result<Vec<String>> {
+    if path.prefix.is_some() || path.drive.is_some() || path.root.is_some() {
+        return Err(escape());
+    }
+
+    let mut segments: Vec<String> = path.segments.iter().map(|s| s.0.clone()).collect();
+
+    if let Some(stream) = path.stream()
+        && let Some(last) = segments.last_mut()
+    {
+        last.push(':');
+        last.push_str(stream.name());
+
+        if let Some(stream_type) = stream.stream_type() {
+            last.push(':');
+            last.push_str(stream_type);
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Resolves `..` in segments which are known to contain no symlinks.
+fn lexical(segments: &[String]) -> Vec<String> {
+    let mut resolved = PathSegmentList::new();
+
+    for segment in segments {
+        match segment.as_str() {
+            "." => {}
+            ".." => {
+                resolved.remove_last();
+            }
+            _ => resolved.push(segment.clone()),
+        }
+    }
+
+    resolved.into_iter().map(|s| s.0).collect()
+}
+
+fn openat2(
+    dirfd: RawFd,
+    segments: &[String],
+    flags: i32,
+    symlinks: SymlinkPolicy,
+) -> io::Result<OwnedFd> {
+    let name = if segments.is_empty() {
+        CString::new(".")?
+    } else {
+        CString::new(segments.join("/"))?
+    };
+
+    // SAFETY: `open_how` is plain integers, for which all zeroes is valid and
+    // asks for no flags, so fields added by newer kernels stay unset.
+    let mut how: libc::open_how = unsafe { core::mem::zeroed() };
+    how.flags = flags as u64;
+    how.resolve = match symlinks {
+        SymlinkPolicy::Deny => libc::RESOLVE_BENEATH | libc::RESOLVE_NO_SYMLINKS,
+        SymlinkPolicy::Beneath => libc::RESOLVE_BENEATH,
+    };
+
+    // SAFETY: `name` is NUL-terminated and `how` is a valid `open_how` of the
+    // size passed, and both outlive the call. A bad `dirfd` fails with `EBADF`.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_openat2,
+            dirfd,
+            name.as_ptr(),
+            &how as *const libc::open_how,
+            core::mem::size_of::<libc::open_how>(),
+        )
+    };
+
+    owned(fd as RawFd)
+}
+
+fn openat(dirfd: RawFd, name: &str, flags: i32) -> io::Result<OwnedFd> {
+    let name = CString::new(name)?;
+    // SAFETY: `name` is a NUL-terminated string which outlives the call.
+    let fd = unsafe { libc::openat(dirfd, name.as_ptr(), flags | libc::O_CLOEXEC) };
+    owned(fd)
+}
+
+fn readlink(fd: &OwnedFd) -> io::Result<String> {
+    let mut buffer = vec![0u8; libc::PATH_MAX as usize];
+
+    // SAFETY: the empty name is NUL-terminated, and `buffer` is writable for
+    // the length passed.
+    let len = unsafe {
+        libc::readlinkat(
+            fd.as_raw_fd(),
+            c"".as_ptr(),
+            buffer.as_mut_ptr().cast(),
+            buffer.len(),
+        )
+    };
+
+    if len < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    buffer.truncate(len as usize);
+    String::from_utf8(buffer).map_err(|_| invalid_input("symlink targets must be valid UTF-8"))
+}
+
+fn stat(fd: &OwnedFd) -> io::Result<libc::stat> {
+    // SAFETY: `stat` is plain integers, for which all zeroes is valid.
+    let mut stat: libc::stat = unsafe { core::mem::zeroed() };
+
+    // SAFETY: `stat` is a valid, writable `stat` for the kernel to fill in.
+    if unsafe { libc::fstat(fd.as_raw_fd(), &mut stat) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(stat)
+}
+
+fn is_symlink(fd: &OwnedFd) -> io::Result<bool> {
+    Ok(stat(fd)?.st_mode & libc::S_IFMT == libc::S_IFLNK)
+}
+
+fn same_file(a: &OwnedFd, b: &OwnedFd) -> io::Result<bool> {
+    let (a, b) = (stat(a)?, stat(b)?);
+    Ok(a.st_dev == b.st_dev && a.st_ino == b.st_ino)
+}
+
+fn owned(fd: RawFd) -> io::Result<OwnedFd> {
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: `fd` was just returned by the kernel, so it's open and nothing
+    // else owns it.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn escape() -> io::Error {
+    io::Error::from_raw_os_error(libc::EXDEV)
+}
+
+fn denied_symlink() -> io::Error {
+    io::Error::from_raw_os_error(libc::ELOOP)
+}
+
+fn invalid_input(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+    use std::io::Read;
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::test_support::TempTree;
+
+    /// Creates a fresh tree in the temp directory:
+    ///
+    /// ```text
+    /// root/a/b.txt
+    /// root/a/c/
+    /// root/inside -> a
+    /// root/a/up -> ../a/b.txt
+    /// root/outside -> ../
+    /// root/absolute -> /etc
+    /// ```
+    fn tree() -> TempTree {
+        TempTree::new("dir")
+            .with_file("a/b.txt", "b")
+            .with_dir("a/c")
+            .with_symlink("a", "inside")
+            .with_symlink("../a/b.txt", "a/up")
+            .with_symlink("../", "outside")
+            .with_symlink("/etc", "absolute")
+    }
+
+    fn open(root: &std::path::Path, symlinks: SymlinkPolicy) -> Dir {
+        Dir::open(Path::try_from(root).unwrap())
+            .unwrap()
+            .with_symlinks(symlinks)
+    }
+
+    #[rstest]
+    #[case(SymlinkPolicy::Deny, "a/b.txt", "a/b.txt")]
+    #[case(SymlinkPolicy::Deny, "a/c/../b.txt", "a/b.txt")]
+    #[case(SymlinkPolicy::Beneath, "a/b.txt", "a/b.txt")]
+    #[case(SymlinkPolicy::Beneath, "inside/b.txt", "a/b.txt")]
+    #[case(SymlinkPolicy::Beneath, "a/up", "a/b.txt")]
+    fn open_file(#[case] symlinks: SymlinkPolicy, #[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let root = tree();
+        let dir = open(root.root(), symlinks);
+
+        // act
+        let (mut file, resolved) = dir.open_file(Path::from_str(path).unwrap()).unwrap();
+        let walked = dir
+            .walk(
+                segments(&Path::from_str(path).unwrap()).unwrap(),
+                libc::O_RDONLY,
+                false,
+            )
+            .unwrap()
+            .1;
+
+        // assert
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "b");
+
+        let expected = dir.path().join(Path::from_str(expected).unwrap()).unwrap();
+        assert_eq!(resolved, expected);
+        assert_eq!(walked, expected);
+    }
+
+    #[rstest]
+    #[case(SymlinkPolicy::Deny, "../x", libc::EXDEV)]
+    #[case(SymlinkPolicy::Deny, "a/../../x", libc::EXDEV)]
+    #[case(SymlinkPolicy::Deny, "/etc/hostname", libc::EXDEV)]
+    #[case(SymlinkPolicy::Deny, "inside/b.txt", libc::ELOOP)]
+    #[case(SymlinkPolicy::Beneath, "outside/x", libc::EXDEV)]
+    #[case(SymlinkPolicy::Beneath, "absolute/hostname", libc::EXDEV)]
+    #[case(SymlinkPolicy::Beneath, "a/missing.txt", libc::ENOENT)]
+    fn open_file_should_fail(
+        #[case] symlinks: SymlinkPolicy,
+        #[case] path: &str,
+        #[case] errno: i32,
+    ) {
+        // arrange
+        let root = tree();
+        let dir = open(root.root(), symlinks);
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let opened = dir.open_file(&path);
+        let walked = segments(&path).and_then(|s| dir.walk(s, libc::O_RDONLY, false));
+
+        // assert
+        assert_eq!(opened.unwrap_err().raw_os_error(), Some(errno));
+        assert_eq!(walked.unwrap_err().raw_os_error(), Some(errno));
+    }
+
+    #[rstest]
+    fn open_dir_confines_to_itself() {
+        // arrange
+        let root = tree();
+        let dir = open(root.root(), SymlinkPolicy::Beneath);
+
+        // act
+        let inner = dir.open_dir(Path::from_str("inside/").unwrap()).unwrap();
+        let escaped = inner.open_file(Path::from_str("../a/b.txt").unwrap());
+        let (_, resolved) = inner.open_file(Path::from_str("b.txt").unwrap()).unwrap();
+
+        // assert
+        assert_eq!(
+            inner.path(),
+            &dir.path().join(Path::from_str("a/").unwrap()).unwrap()
+        );
+        assert_eq!(escaped.unwrap_err().raw_os_error(), Some(libc::EXDEV));
+        assert_eq!(
+            resolved,
+            dir.path().join(Path::from_str("a/b.txt").unwrap()).unwrap()
+        );
+    }
+}
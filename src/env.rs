@@ -0,0 +1,536 @@
+use core::{fmt, str::FromStr};
+
+use alloc::{collections::BTreeMap, string::String};
+
+#[cfg(feature = "std")]
+use crate::users::Passwd;
+use crate::{path::Path, users::UserDatabase};
+
+/// A source of environment variables for [`Path::expand_env`].
+pub trait EnvSource {
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+/// Everything outside of a path which resolving it depends on: the working
+/// directory, home directories and environment variables.
+///
+/// [`ProcessEnv`] reads these from the current process, while [`MemoryEnv`]
+/// holds them as plain data for tests, `no_std` targets and servers which
+/// resolve paths on behalf of several clients.
+pub trait PathEnvironment: EnvSource + UserDatabase {
+    fn current_dir(&self) -> Result<Path, &'static str>;
+
+    fn home_dir(&self) -> Option<Path>;
+}
+
+impl<T: PathEnvironment + ?Sized> PathEnvironment for &T {
+    fn current_dir(&self) -> Result<Path, &'static str> {
+        (**self).current_dir()
+    }
+
+    fn home_dir(&self) -> Option<Path> {
+        (**self).home_dir()
+    }
+}
+
+impl EnvSource for BTreeMap<String, String> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+impl<T: EnvSource + ?Sized> EnvSource for &T {
+    fn var(&self, name: &str) -> Option<String> {
+        (**self).var(name)
+    }
+}
+
+/// The environment of the current process. Users are looked up in
+/// `/etc/passwd`.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessEnv;
+
+#[cfg(feature = "std")]
+impl EnvSource for ProcessEnv {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+#[cfg(feature = "std")]
+impl UserDatabase for ProcessEnv {
+    fn user_home_dir(&self, user: &str) -> Option<Path> {
+        Passwd::default().user_home_dir(user)
+    }
+}
+
+#[cfg(feature = "std")]
+impl PathEnvironment for ProcessEnv {
+    fn current_dir(&self) -> Result<Path, &'static str> {
+        let cwd = std::env::current_dir().map_err(|_| "failed to get cwd")?;
+        let mut cwd = Path::try_from(cwd)?;
+        cwd.is_dir = true;
+        Ok(cwd)
+    }
+
+    fn home_dir(&self) -> Option<Path> {
+        let home = dirs::home_dir()?;
+        Path::try_from(home).ok()
+    }
+}
+
+/// An environment held in memory. Nothing is set until it's added.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryEnv {
+    current_dir: Option<Path>,
+    home_dir: Option<Path>,
+    users: BTreeMap<String, Path>,
+    vars: BTreeMap<String, String>,
+}
+
+impl MemoryEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_current_dir(mut self, current_dir: impl AsRef<Path>) -> Self {
+        let mut current_dir = current_dir.as_ref().clone();
+        current_dir.is_dir = true;
+        self.current_dir = Some(current_dir);
+        self
+    }
+
+    pub fn with_home_dir(mut self, home_dir: impl AsRef<Path>) -> Self {
+        self.home_dir = Some(home_dir.as_ref().clone());
+        self
+    }
+
+    pub fn with_user(mut self, user: impl Into<String>, home_dir: impl AsRef<Path>) -> Self {
+        self.users.insert(user.into(), home_dir.as_ref().clone());
+        self
+    }
+
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+}
+
+impl EnvSource for MemoryEnv {
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned()
+    }
+}
+
+impl UserDatabase for MemoryEnv {
+    fn user_home_dir(&self, user: &str) -> Option<Path> {
+        self.users.get(user).cloned()
+    }
+}
+
+impl PathEnvironment for MemoryEnv {
+    fn current_dir(&self) -> Result<Path, &'static str> {
+        self.current_dir
+            .clone()
+            .ok_or("no current directory is set")
+    }
+
+    fn home_dir(&self) -> Option<Path> {
+        self.home_dir.clone()
+    }
+}
+
+/// The syntax variables are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvFlavor {
+    /// `$VAR`, `${VAR}`, `${VAR:-default}`, `${VAR-default}`, `${VAR:?message}`
+    /// and `${VAR?message}`, with `$$` for a literal `$`. Unset variables expand
+    /// to nothing.
+    Posix,
+    /// `%VAR%`, with `%%` for a literal `%`. Unset variables are left as they
+    /// are, the way `cmd.exe` leaves them.
+    Cmd,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    /// A `${VAR?message}` or `${VAR:?message}` variable was unset.
+    Unset { name: String, message: String },
+    /// The input had malformed variable syntax.
+    Syntax(&'static str),
+    /// The expanded input wasn't a valid path.
+    Path(&'static str),
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Unset { name, message } if message.is_empty() => {
+                write!(f, "{name}: parameter null or not set")
+            }
+            EnvError::Unset { name, message } => write!(f, "{name}: {message}"),
+            EnvError::Syntax(message) | EnvError::Path(message) => f.write_str(message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EnvError {}
+
+impl Path {
+    /// Expands the environment variables in `input`, then parses it as a path,
+    /// so a variable holding `a/b` produces two segments and a default which
+    /// isn't used never has to be a valid path.
+    pub fn from_str_expanded(
+        input: &str,
+        env: &impl EnvSource,
+        flavor: EnvFlavor,
+    ) -> Result<Self, EnvError> {
+        Path::from_str(&expand(input, env, flavor)?).map_err(EnvError::Path)
+    }
+
+    /// Expands the environment variables in this path, which was already
+    /// parsed. The expansion happens on the rendered path, so a variable
+    /// holding `a/b` still produces two segments, but anything the parser
+    /// rejects has to go through [`Path::from_str_expanded`] instead.
+    pub fn expand_env(&self, env: &impl EnvSource, flavor: EnvFlavor) -> Result<Self, EnvError> {
+        Path::from_str_expanded(&self.rendered(), env, flavor)
+    }
+}
+
+/// Expands the environment variables in `input`.
+pub fn expand(input: &str, env: &impl EnvSource, flavor: EnvFlavor) -> Result<String, EnvError> {
+    match flavor {
+        EnvFlavor::Posix => expand_posix(input, env),
+        EnvFlavor::Cmd => Ok(expand_cmd(input, env)),
+    }
+}
+
+fn expand_posix(input: &str, env: &impl EnvSource) -> Result<String, EnvError> {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(dollar) = rest.find('$') {
+        result.push_str(&rest[..dollar]);
+        rest = &rest[dollar + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = closing_brace(after).ok_or(EnvError::Syntax("unterminated ${"))?;
+            result.push_str(&expand_braced(&after[..end], env)?);
+            rest = &after[end + 1..];
+        } else {
+            let len = name_len(rest);
+
+            if len == 0 {
+                result.push('$');
+            } else {
+                result.push_str(&env.var(&rest[..len]).unwrap_or_default());
+                rest = &rest[len..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Expands the inside of `${...}`.
+fn expand_braced(inner: &str, env: &impl EnvSource) -> Result<String, EnvError> {
+    let len = name_len(inner);
+
+    if len == 0 {
+        return Err(EnvError::Syntax("bad substitution"));
+    }
+
+    let (name, operator) = inner.split_at(len);
+    let value = env.var(name);
+
+    let (null_counts_as_unset, operator) = match operator.strip_prefix(':') {
+        Some(operator) => (true, operator),
+        None => (false, operator),
+    };
+
+    let is_unset = match &value {
+        None => true,
+        Some(value) => null_counts_as_unset && value.is_empty(),
+    };
+
+    if operator.is_empty() && !null_counts_as_unset {
+        return Ok(value.unwrap_or_default());
+    }
+
+    if let Some(word) = operator.strip_prefix('-') {
+        return if is_unset {
+            expand_posix(word, env)
+        } else {
+            Ok(value.unwrap_or_default())
+        };
+    }
+
+    if let Some(message) = operator.strip_prefix('?') {
+        return if is_unset {
+            Err(EnvError::Unset {
+                name: name.into(),
+                message: expand_posix(message, env)?,
+            })
+        } else {
+            Ok(value.unwrap_or_default())
+        };
+    }
+
+    Err(EnvError::Syntax("bad substitution"))
+}
+
+/// Finds the `}` closing a `${`, skipping over nested `${...}`.
+fn closing_brace(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '$' if chars.peek().is_some_and(|(_, c)| *c == '{') => {
+                chars.next();
+                depth += 1;
+            }
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn name_len(input: &str) -> usize {
+    input
+        .char_indices()
+        .take_while(|(i, c)| c.is_ascii_alphabetic() || *c == '_' || (*i > 0 && c.is_ascii_digit()))
+        .count()
+}
+
+fn expand_cmd(input: &str, env: &impl EnvSource) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(percent) = rest.find('%') {
+        result.push_str(&rest[..percent]);
+        rest = &rest[percent + 1..];
+
+        if let Some(after) = rest.strip_prefix('%') {
+            result.push('%');
+            rest = after;
+            continue;
+        }
+
+        let Some(end) = rest.find('%') else {
+            result.push('%');
+            continue;
+        };
+
+        match env.var(&rest[..end]) {
+            Some(value) => {
+                result.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            None => result.push('%'),
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::ToString;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::path::Resolution;
+
+    fn env() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("XDG_CACHE_HOME".to_string(), "/home/me/.cache".to_string()),
+            (
+                "APPDATA".to_string(),
+                r"C:\Users\me\AppData\Roaming".to_string(),
+            ),
+            ("NESTED".to_string(), "a/b".to_string()),
+            ("EMPTY".to_string(), "".to_string()),
+            ("NAME".to_string(), "app".to_string()),
+        ])
+    }
+
+    #[rstest]
+    #[case("$XDG_CACHE_HOME/app", "/home/me/.cache/app")]
+    #[case("${XDG_CACHE_HOME}/app", "/home/me/.cache/app")]
+    #[case("${NAME}s", "apps")]
+    #[case("$NAMEs", "")]
+    #[case("$UNSET/x", "/x")]
+    #[case("${BUILD_DIR:-target}/out", "target/out")]
+    #[case("${BUILD_DIR-target}/out", "target/out")]
+    #[case("${EMPTY:-target}/out", "target/out")]
+    #[case("${EMPTY-target}/out", "/out")]
+    #[case("${NAME:-target}/out", "app/out")]
+    #[case("${BUILD_DIR:-$XDG_CACHE_HOME/$NAME}", "/home/me/.cache/app")]
+    #[case("${BUILD_DIR:-${NAME}-build}", "app-build")]
+    #[case("${NAME:?is required}", "app")]
+    #[case("${EMPTY?is required}", "")]
+    #[case("cost$$/x", "cost$/x")]
+    #[case("a$/b", "a$/b")]
+    #[case("a/$", "a/$")]
+    #[case("%APPDATA%", "%APPDATA%")]
+    fn expand_posix(#[case] input: &str, #[case] expected: &str) {
+        // act
+        let expanded = expand(input, &env(), EnvFlavor::Posix).unwrap();
+
+        // assert
+        assert_eq!(expanded, expected);
+    }
+
+    #[rstest]
+    #[case("${BUILD_DIR:?must be set}", "BUILD_DIR", "must be set")]
+    #[case("${EMPTY:?must not be empty}", "EMPTY", "must not be empty")]
+    #[case("${BUILD_DIR?}", "BUILD_DIR", "")]
+    #[case("${BUILD_DIR?$NAME needs it}", "BUILD_DIR", "app needs it")]
+    fn expand_posix_unset(#[case] input: &str, #[case] name: &str, #[case] message: &str) {
+        // act
+        let expanded = expand(input, &env(), EnvFlavor::Posix);
+
+        // assert
+        assert_eq!(
+            expanded,
+            Err(EnvError::Unset {
+                name: name.to_string(),
+                message: message.to_string()
+            })
+        );
+    }
+
+    #[rstest]
+    #[case("${NAME")]
+    #[case("${}")]
+    #[case("${1abc}")]
+    #[case("${NAME:+x}")]
+    #[case("${NAME%x}")]
+    fn expand_posix_should_fail(#[case] input: &str) {
+        // act
+        let expanded = expand(input, &env(), EnvFlavor::Posix);
+
+        // assert
+        assert!(matches!(expanded, Err(EnvError::Syntax(_))));
+    }
+
+    #[rstest]
+    #[case(r"%APPDATA%\App", r"C:\Users\me\AppData\Roaming\App")]
+    #[case(r"%NAME%%NAME%", "appapp")]
+    #[case(r"%UNSET%\x", r"%UNSET%\x")]
+    #[case(r"100%%\x", r"100%\x")]
+    #[case(r"100%\x", r"100%\x")]
+    #[case(r"a%b%NAME%", r"a%bapp")]
+    #[case("$NAME", "$NAME")]
+    fn expand_cmd(#[case] input: &str, #[case] expected: &str) {
+        // act
+        let expanded = expand(input, &env(), EnvFlavor::Cmd).unwrap();
+
+        // assert
+        assert_eq!(expanded, expected);
+    }
+
+    #[rstest]
+    #[case("$XDG_CACHE_HOME/app", EnvFlavor::Posix, "/home/me/.cache/app")]
+    #[case(
+        "${BUILD_DIR:-target/debug}/out/",
+        EnvFlavor::Posix,
+        "target/debug/out/"
+    )]
+    #[case("x/$NESTED/y", EnvFlavor::Posix, "x/a/b/y")]
+    #[case(r"%APPDATA%\App", EnvFlavor::Cmd, r"C:\Users\me\AppData\Roaming\App")]
+    #[case(r"x\%NESTED%", EnvFlavor::Cmd, "x/a/b")]
+    fn path_expand_env(#[case] path: &str, #[case] flavor: EnvFlavor, #[case] expected: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let expanded = path.expand_env(&env(), flavor).unwrap();
+
+        // assert
+        let expected = Path::from_str(expected).unwrap();
+        assert_eq!(expanded, expected);
+        assert_eq!(expanded.segments.len(), expected.segments.len());
+    }
+
+    #[rstest]
+    #[case("${NAME:-a//b}/c", EnvFlavor::Posix, "app/c")]
+    #[case("${UNSET:-a/b}/c", EnvFlavor::Posix, "a/b/c")]
+    #[case("$NESTED/x", EnvFlavor::Posix, "a/b/x")]
+    #[case(r"%NAME%\x", EnvFlavor::Cmd, "app/x")]
+    fn path_from_str_expanded(
+        #[case] input: &str,
+        #[case] flavor: EnvFlavor,
+        #[case] expected: &str,
+    ) {
+        // act
+        let expanded = Path::from_str_expanded(input, &env(), flavor);
+
+        // assert
+        assert_eq!(expanded, Ok(Path::from_str(expected).unwrap()));
+    }
+
+    #[rstest]
+    #[case("x/${EMPTY}/y")]
+    #[case("${UNSET:-a//b}")]
+    fn path_from_str_expanded_to_invalid_path(#[case] input: &str) {
+        // act
+        let expanded = Path::from_str_expanded(input, &env(), EnvFlavor::Posix);
+
+        // assert
+        assert!(matches!(expanded, Err(EnvError::Path(_))));
+    }
+
+    #[rstest]
+    fn path_expand_env_to_invalid_path() {
+        // arrange
+        let path = Path::from_str("x/${EMPTY}/y").unwrap();
+
+        // act
+        let expanded = path.expand_env(&env(), EnvFlavor::Posix);
+
+        // assert
+        assert!(matches!(expanded, Err(EnvError::Path(_))));
+    }
+
+    #[rstest]
+    fn memory_env() {
+        // arrange
+        let env = MemoryEnv::new()
+            .with_current_dir(Path::from_str("/work").unwrap())
+            .with_home_dir(Path::from_str("/home/me").unwrap())
+            .with_user("alice", Path::from_str("/home/alice").unwrap())
+            .with_var("PROJECT", "canonic");
+        let path = Path::from_str("~/src/$PROJECT").unwrap();
+
+        // act
+        let resolved = path
+            .expand_env(&env, EnvFlavor::Posix)
+            .unwrap()
+            .resolve_with(&env, Resolution::Lexical)
+            .unwrap();
+
+        // assert
+        assert_eq!(resolved, Path::from_str("/home/me/src/canonic").unwrap());
+        assert_eq!(
+            env.current_dir().unwrap(),
+            Path::from_str("/work/").unwrap()
+        );
+        assert_eq!(
+            env.user_home_dir("alice"),
+            Some(Path::from_str("/home/alice").unwrap())
+        );
+        assert!(MemoryEnv::new().current_dir().is_err());
+    }
+}
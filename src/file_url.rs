@@ -0,0 +1,289 @@
+use core::str::FromStr;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::path::{Path, Prefix, Root};
+
+impl Path {
+    /// Parses a `file://` url such as `file:///C:/Users/a%20b/x.rs` or
+    /// `file://server/share/x`.
+    ///
+    /// An empty or `localhost` host refers to the local machine, any other host
+    /// becomes the server of a UNC path. Drive letters are recognised whether the
+    /// colon is written out or percent-encoded, as in `file:///c%3A/`.
+    pub fn from_file_url(url: &str) -> Result<Self, &'static str> {
+        let rest = strip_scheme(url).ok_or("file urls must start with file:")?;
+        let rest = &rest[..rest.find(['?', '#']).unwrap_or(rest.len())];
+
+        let (host, path) = match rest.strip_prefix("//") {
+            Some(rest) => rest.split_at(rest.find('/').unwrap_or(rest.len())),
+            None => ("", rest),
+        };
+
+        if !path.is_empty() && !path.starts_with('/') {
+            return Err("file url paths must be absolute");
+        }
+
+        let mut path = percent_decode(path)?;
+
+        if host.is_empty() || host.eq_ignore_ascii_case("localhost") {
+            let bytes = path.as_bytes();
+
+            // `/C:/...` and the legacy `/C|/...` spelling
+            if bytes.len() >= 3
+                && bytes[1].is_ascii_alphabetic()
+                && matches!(bytes[2], b':' | b'|')
+                && bytes.get(3).is_none_or(|b| *b == b'/')
+            {
+                path.remove(0);
+                path.replace_range(1..2, ":");
+
+                if path.len() == 2 {
+                    path.push('/');
+                }
+            }
+
+            if path.is_empty() {
+                path.push('/');
+            }
+
+            Path::from_str(&path)
+        } else {
+            let host = percent_decode(host)?;
+
+            if host.contains(['/', '\\', ':']) {
+                return Err("file url hosts must be plain server names");
+            }
+
+            let mut unc = String::with_capacity(host.len() + path.len() + 2);
+            unc.push_str("//");
+            unc.push_str(&host);
+            unc.push_str(&path);
+            Path::from_str(&unc)
+        }
+    }
+
+    /// Renders this path as a `file://` url, percent-encoding every character
+    /// which isn't allowed in a url path. Only absolute paths have a url.
+    pub fn to_file_url(&self) -> Result<String, &'static str> {
+        let mut url = String::from("file://");
+
+        match (&self.prefix, &self.drive, &self.root) {
+            (Some(Prefix::Device), _, _) => {
+                return Err("device paths cannot be represented as file urls");
+            }
+            (_, _, None) => return Err("only absolute paths can be represented as file urls"),
+            (_, Some(drive), Some(Root::Normal)) => {
+                url.push('/');
+                url.push(drive.letter);
+                url.push(':');
+            }
+            _ => {}
+        }
+
+        let mut segments = self.segments.iter();
+
+        if self.root == Some(Root::Unc) {
+            let host = segments
+                .next()
+                .ok_or("unc paths need a server to be represented as file urls")?;
+
+            percent_encode(&host.0, &mut url);
+        }
+
+        let mut segments = segments.peekable();
+
+        if segments.peek().is_none() {
+            url.push('/');
+        }
+
+        while let Some(segment) = segments.next() {
+            url.push('/');
+            percent_encode(&segment.0, &mut url);
+
+            if segments.peek().is_none() {
+                if let Some(stream) = &self.stream {
+                    url.push(':');
+                    percent_encode(&stream.name, &mut url);
+
+                    if let Some(stream_type) = &stream.stream_type {
+                        url.push(':');
+                        percent_encode(stream_type, &mut url);
+                    }
+                } else if self.is_dir {
+                    url.push('/');
+                }
+            }
+        }
+
+        Ok(url)
+    }
+}
+
+fn strip_scheme(url: &str) -> Option<&str> {
+    let (scheme, rest) = url.split_once(':')?;
+    scheme.eq_ignore_ascii_case("file").then_some(rest)
+}
+
+fn percent_encode(input: &str, output: &mut String) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&byte) {
+            output.push(byte as char);
+        } else {
+            output.push('%');
+            output.push(HEX[(byte >> 4) as usize] as char);
+            output.push(HEX[(byte & 0xf) as usize] as char);
+        }
+    }
+}
+
+fn percent_decode(input: &str) -> Result<String, &'static str> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut iter = input.bytes();
+
+    while let Some(byte) = iter.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let high = iter.next().and_then(|b| (b as char).to_digit(16));
+        let low = iter.next().and_then(|b| (b as char).to_digit(16));
+
+        let (Some(high), Some(low)) = (high, low) else {
+            return Err("file urls must only contain valid percent-encodings");
+        };
+
+        let decoded = (high * 16 + low) as u8;
+
+        if decoded == b'/' || decoded == b'\\' {
+            return Err("file urls cannot contain percent-encoded separators");
+        }
+
+        bytes.push(decoded);
+    }
+
+    String::from_utf8(bytes).map_err(|_| "file urls must decode to valid UTF-8")
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("file:///home/alice/file.txt", "/home/alice/file.txt")]
+    #[case("file:///home/alice/dir/", "/home/alice/dir/")]
+    #[case("file:///", "/")]
+    #[case("file:///C:/Users/a%20b/x.rs", "C:/Users/a b/x.rs")]
+    #[case("file:///c%3A/Users/x.rs", "c:/Users/x.rs")]
+    #[case("file:///c%3a/Users/x.rs", "c:/Users/x.rs")]
+    #[case("file:///C|/Users/x.rs", "C:/Users/x.rs")]
+    #[case("file:///C:", "C:/")]
+    #[case("file:///C:/", "C:/")]
+    #[case("file://localhost/etc/hosts", "/etc/hosts")]
+    #[case("file://LOCALHOST/C:/x", "C:/x")]
+    #[case("file:/etc/hosts", "/etc/hosts")]
+    #[case("FILE:///etc/hosts", "/etc/hosts")]
+    #[case("file://server/share/x", "//server/share/x")]
+    #[case("file://server/share/a%20b/", "//server/share/a b/")]
+    #[case("file:///tmp/%C3%A6%C3%B8%C3%A5.txt", "/tmp/æøå.txt")]
+    #[case("file:///tmp/a%23b.txt", "/tmp/a#b.txt")]
+    #[case("file:///tmp/x.txt?query#fragment", "/tmp/x.txt")]
+    #[case("file:///C:/notes.txt:Zone.Identifier", "C:/notes.txt:Zone.Identifier")]
+    fn from_file_url(#[case] url: &str, #[case] expected: &str) {
+        // act
+        let path = Path::from_file_url(url).unwrap();
+
+        // assert
+        assert_eq!(path, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case("http://example.com/x")]
+    #[case("/etc/hosts")]
+    #[case("file:relative/path")]
+    #[case("file:///tmp/a%2Fb")]
+    #[case("file:///tmp/a%5Cb")]
+    #[case("file:///tmp/a%zzb")]
+    #[case("file:///tmp/a%2")]
+    #[case("file:///tmp/%FF")]
+    #[case("file://server:80/share")]
+    fn from_file_url_should_fail(#[case] url: &str) {
+        // act
+        let path = Path::from_file_url(url);
+
+        // assert
+        assert!(path.is_err());
+    }
+
+    #[rstest]
+    #[case("/home/alice/file.txt", "file:///home/alice/file.txt")]
+    #[case("/home/alice/dir/", "file:///home/alice/dir/")]
+    #[case("/", "file:///")]
+    #[case(r"C:\Users\a b\x.rs", "file:///C:/Users/a%20b/x.rs")]
+    #[case(r"C:\", "file:///C:/")]
+    #[case(r"\\server\share\x", "file://server/share/x")]
+    #[case(r"\\?\C:\x", "file:///C:/x")]
+    #[case(r"\\?\UNC\server\share\x", "file://server/share/x")]
+    #[case("/tmp/æøå.txt", "file:///tmp/%C3%A6%C3%B8%C3%A5.txt")]
+    #[case("/tmp/a#b?c%d.txt", "file:///tmp/a%23b%3Fc%25d.txt")]
+    #[case("/tmp/[x] {y}", "file:///tmp/%5Bx%5D%20%7By%7D")]
+    #[case(
+        r"C:\notes.txt:Zone.Identifier",
+        "file:///C:/notes.txt:Zone.Identifier"
+    )]
+    fn to_file_url(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let url = path.to_file_url().unwrap();
+
+        // assert
+        assert_eq!(url, expected);
+    }
+
+    #[rstest]
+    #[case("a/b")]
+    #[case("~/a")]
+    #[case("C:a")]
+    #[case(r"\\.\COM1")]
+    #[case(r"\\")]
+    fn to_file_url_should_fail(#[case] path: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let url = path.to_file_url();
+
+        // assert
+        assert!(url.is_err());
+    }
+
+    #[rstest]
+    #[case("/home/alice/file.txt")]
+    #[case("/home/alice/dir/")]
+    #[case("/tmp/a b/100%/#1?.txt")]
+    #[case("/")]
+    #[case(r"C:\Users\Alice\Documents\file.txt")]
+    #[case(r"D:\Projects\code.py")]
+    #[case(r"C:\")]
+    #[case(r"\\Server\Share\folder\file.txt")]
+    #[case(r"\\Server\Share\")]
+    #[case(r"C:\notes.txt:Zone.Identifier")]
+    #[case(r"C:\file.txt::$DATA")]
+    fn round_trip(#[case] path: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let round_tripped = Path::from_file_url(&path.to_file_url().unwrap()).unwrap();
+
+        // assert
+        assert_eq!(round_tripped, path);
+    }
+}
@@ -0,0 +1,661 @@
+use core::{fmt, str::FromStr};
+
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    env::MemoryEnv,
+    path::{Path, Resolution},
+    physical::{DEFAULT_SYMLINK_HOPS, PhysicalError},
+};
+
+/// The kind of an entry in a [`FileSystem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    File,
+    Dir,
+    Symlink,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    file_type: FileType,
+    len: u64,
+}
+
+impl Metadata {
+    pub fn new(file_type: FileType, len: u64) -> Self {
+        Metadata { file_type, len }
+    }
+
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.file_type == FileType::File
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type == FileType::Dir
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.file_type == FileType::Symlink
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// An entry of a directory, as listed by [`FileSystem::read_dir`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    name: String,
+    file_type: FileType,
+}
+
+impl DirEntry {
+    pub fn new(name: impl Into<String>, file_type: FileType) -> Self {
+        DirEntry {
+            name: name.into(),
+            file_type,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The type of the entry itself, so symlinks are reported as symlinks.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsError {
+    NotFound(String),
+    NotADirectory(String),
+    NotASymlink(String),
+    /// Too many symlinks were followed while looking up the path.
+    Loop(String),
+    /// Any other failure, as reported by the filesystem.
+    Other(String),
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (path, message) = match self {
+            FsError::NotFound(path) => (path, "no such file or directory"),
+            FsError::NotADirectory(path) => (path, "not a directory"),
+            FsError::NotASymlink(path) => (path, "not a symbolic link"),
+            FsError::Loop(path) => (path, "too many levels of symbolic links"),
+            FsError::Other(message) => return f.write_str(message),
+        };
+
+        write!(f, "{path}: {message}")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FsError {}
+
+/// The filesystem operations paths are resolved with.
+///
+/// [`StdFs`] is the host filesystem, while [`MemoryFs`] is held in memory for
+/// tests and can stand in for remote filesystems.
+pub trait FileSystem {
+    /// Returns the metadata of `path`, without following a final symlink.
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError>;
+
+    /// Returns the target of the symlink at `path`, as it was written.
+    fn read_link(&self, path: &Path) -> Result<Path, FsError>;
+
+    /// Lists the entries of the directory at `path`, in no particular order.
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, FsError>;
+
+    /// Returns the absolute path `path` refers to, with every symlink followed
+    /// and every `.` and `..` removed. All of it must exist.
+    fn canonicalize(&self, path: &Path) -> Result<Path, FsError> {
+        let resolved = path.resolve_physical_in(self).map_err(|e| match e {
+            PhysicalError::Fs(e) => e,
+            PhysicalError::Loop { .. } => FsError::Loop(path.rendered()),
+            PhysicalError::Path(message) => FsError::Other(message.to_string()),
+        })?;
+
+        let mut resolved = resolved;
+        resolved.is_dir = self.symlink_metadata(&resolved)?.is_dir();
+        Ok(resolved)
+    }
+
+    /// Returns the metadata of `path`, following a final symlink.
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.symlink_metadata(&self.canonicalize(path)?)
+    }
+}
+
+impl<T: FileSystem + ?Sized> FileSystem for &T {
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        (**self).symlink_metadata(path)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<Path, FsError> {
+        (**self).read_link(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, FsError> {
+        (**self).read_dir(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<Path, FsError> {
+        (**self).canonicalize(path)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        (**self).metadata(path)
+    }
+}
+
+/// The filesystem of the host, through `std::fs`.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StdFs;
+
+#[cfg(feature = "std")]
+impl StdFs {
+    fn error(path: &Path, error: std::io::Error) -> FsError {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => FsError::NotFound(path.rendered()),
+            std::io::ErrorKind::NotADirectory => FsError::NotADirectory(path.rendered()),
+            _ => FsError::Other(error.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn std_metadata(metadata: std::fs::Metadata) -> Metadata {
+    let file_type = if metadata.is_symlink() {
+        FileType::Symlink
+    } else if metadata.is_dir() {
+        FileType::Dir
+    } else {
+        FileType::File
+    };
+
+    Metadata::new(file_type, metadata.len())
+}
+
+#[cfg(feature = "std")]
+impl FileSystem for StdFs {
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        std::fs::symlink_metadata(path.to_std_path())
+            .map(std_metadata)
+            .map_err(|e| StdFs::error(path, e))
+    }
+
+    fn read_link(&self, path: &Path) -> Result<Path, FsError> {
+        let target = std::fs::read_link(path.to_std_path()).map_err(|e| match e.kind() {
+            std::io::ErrorKind::InvalidInput => FsError::NotASymlink(path.rendered()),
+            _ => StdFs::error(path, e),
+        })?;
+
+        Path::try_from(target).map_err(|e| FsError::Other(e.to_string()))
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, FsError> {
+        let entries = std::fs::read_dir(path.to_std_path()).map_err(|e| StdFs::error(path, e))?;
+
+        entries
+            .map(|entry| {
+                let entry = entry.map_err(|e| StdFs::error(path, e))?;
+                let file_type = entry.file_type().map_err(|e| StdFs::error(path, e))?;
+
+                let file_type = if file_type.is_symlink() {
+                    FileType::Symlink
+                } else if file_type.is_dir() {
+                    FileType::Dir
+                } else {
+                    FileType::File
+                };
+
+                let name = entry
+                    .file_name()
+                    .into_string()
+                    .map_err(|_| FsError::Other("file names must be valid UTF-8".to_string()))?;
+
+                Ok(DirEntry::new(name, file_type))
+            })
+            .collect()
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<Path, FsError> {
+        let canonical =
+            std::fs::canonicalize(path.to_std_path()).map_err(|e| StdFs::error(path, e))?;
+        let mut canonical = Path::try_from(canonical).map_err(|e| FsError::Other(e.to_string()))?;
+        canonical.is_dir = canonical.is_canonic_dir();
+        Ok(canonical)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        std::fs::metadata(path.to_std_path())
+            .map(std_metadata)
+            .map_err(|e| StdFs::error(path, e))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    File(u64),
+    Dir,
+    Symlink(Path),
+}
+
+/// A filesystem held in memory. It can hold anything a real filesystem can,
+/// including dangling symlinks and symlink loops.
+///
+/// Every path in it is absolute. Parent directories are created as entries are
+/// added, and roots such as `/` or `C:\` always exist.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryFs {
+    entries: BTreeMap<Vec<String>, Entry>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file of `len` bytes at `path`, replacing what was there.
+    pub fn with_file(self, path: impl AsRef<Path>, len: u64) -> Result<Self, &'static str> {
+        self.with_entry(path.as_ref(), Entry::File(len))
+    }
+
+    /// Adds a directory at `path`, replacing what was there.
+    pub fn with_dir(self, path: impl AsRef<Path>) -> Result<Self, &'static str> {
+        self.with_entry(path.as_ref(), Entry::Dir)
+    }
+
+    /// Adds a symlink at `path` pointing at `target`, replacing what was there.
+    /// The target doesn't need to exist.
+    pub fn with_symlink(
+        self,
+        path: impl AsRef<Path>,
+        target: impl AsRef<Path>,
+    ) -> Result<Self, &'static str> {
+        self.with_entry(path.as_ref(), Entry::Symlink(target.as_ref().clone()))
+    }
+
+    fn with_entry(mut self, path: &Path, entry: Entry) -> Result<Self, &'static str> {
+        // Entries never depend on the process, so `~` is left to fail as a
+        // relative path.
+        let path = path
+            .clone()
+            .resolve_keeping_tilde(&MemoryEnv::new(), Resolution::Lexical)?;
+        let key = key(&path)?;
+
+        if key.len() == 1 {
+            return Err("the root of a memory filesystem cannot be replaced");
+        }
+
+        for len in 2..key.len() {
+            self.entries
+                .entry(key[..len].to_vec())
+                .or_insert(Entry::Dir);
+        }
+
+        self.entries.insert(key, entry);
+        Ok(self)
+    }
+
+    fn get(&self, key: &[String]) -> Option<&Entry> {
+        if key.len() == 1 {
+            return Some(&Entry::Dir);
+        }
+
+        self.entries.get(key)
+    }
+
+    /// Looks `path` up one segment at a time, following symlinks along the way
+    /// and, if `follow` is set, at the end.
+    fn lookup(&self, path: &Path, follow: bool) -> Result<Vec<String>, FsError> {
+        let mut current = key(path).map_err(|_| FsError::NotFound(path.rendered()))?;
+        let mut queue: VecDeque<String> = current.drain(1..).collect();
+        let mut hops = 0;
+
+        while let Some(segment) = queue.pop_front() {
+            match segment.as_str() {
+                "." => continue,
+                ".." => {
+                    if current.len() > 1 {
+                        current.pop();
+                    }
+
+                    continue;
+                }
+                _ => current.push(segment),
+            }
+
+            match self.get(&current) {
+                None => return Err(FsError::NotFound(path.rendered())),
+                Some(Entry::Symlink(target)) if follow || !queue.is_empty() => {
+                    hops += 1;
+
+                    if hops > DEFAULT_SYMLINK_HOPS {
+                        return Err(FsError::Loop(path.rendered()));
+                    }
+
+                    current.pop();
+
+                    let mut target_key = key(target).unwrap_or_default();
+
+                    if target.is_absolute() {
+                        current = vec![target_key.remove(0)];
+                    } else {
+                        target_key = target.segments.iter().map(|s| s.0.clone()).collect();
+                    }
+
+                    for segment in target_key.into_iter().rev() {
+                        queue.push_front(segment);
+                    }
+                }
+                Some(Entry::File(_)) if !queue.is_empty() => {
+                    return Err(FsError::NotADirectory(path.rendered()));
+                }
+                Some(_) => {}
+            }
+        }
+
+        Ok(current)
+    }
+}
+
+/// The key of a path in a [`MemoryFs`]: its rendered root, then its segments.
+fn key(path: &Path) -> Result<Vec<String>, &'static str> {
+    if path.is_relative() {
+        return Err("memory filesystems only hold absolute paths");
+    }
+
+    let root = Path {
+        prefix: path.prefix.clone(),
+        drive: path.drive.clone(),
+        root: path.root.clone(),
+        ..Path::new()
+    };
+
+    let mut key = vec![root.builder().build_string()];
+    key.extend(path.segments.iter().map(|s| s.0.clone()));
+    Ok(key)
+}
+
+fn key_to_path(key: &[String], is_dir: bool) -> Path {
+    let mut path = Path::from_str(&key[0]).expect("keys start with a valid root");
+
+    for segment in &key[1..] {
+        path.segments.push(segment.clone());
+    }
+
+    path.is_dir = is_dir;
+    path
+}
+
+impl FileSystem for MemoryFs {
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        let key = self.lookup(path, false)?;
+
+        Ok(match self.get(&key) {
+            Some(Entry::File(len)) => Metadata::new(FileType::File, *len),
+            Some(Entry::Dir) => Metadata::new(FileType::Dir, 0),
+            Some(Entry::Symlink(target)) => {
+                let len = target.clone().builder().build_string().len() as u64;
+                Metadata::new(FileType::Symlink, len)
+            }
+            None => return Err(FsError::NotFound(path.rendered())),
+        })
+    }
+
+    fn read_link(&self, path: &Path) -> Result<Path, FsError> {
+        let key = self.lookup(path, false)?;
+
+        match self.get(&key) {
+            Some(Entry::Symlink(target)) => Ok(target.clone()),
+            _ => Err(FsError::NotASymlink(path.rendered())),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, FsError> {
+        let key = self.lookup(path, true)?;
+
+        if self.get(&key) != Some(&Entry::Dir) {
+            return Err(FsError::NotADirectory(path.rendered()));
+        }
+
+        Ok(self
+            .entries
+            .iter()
+            .filter(|(k, _)| k.len() == key.len() + 1 && k.starts_with(&key))
+            .map(|(k, entry)| {
+                let file_type = match entry {
+                    Entry::File(_) => FileType::File,
+                    Entry::Dir => FileType::Dir,
+                    Entry::Symlink(_) => FileType::Symlink,
+                };
+
+                DirEntry::new(k[key.len()].clone(), file_type)
+            })
+            .collect())
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<Path, FsError> {
+        let key = self.lookup(path, true)?;
+        Ok(key_to_path(&key, self.get(&key) == Some(&Entry::Dir)))
+    }
+}
+
+impl Path {
+    /// Returns whether this path exists in `fs`, following symlinks.
+    pub fn exists_in(&self, fs: &(impl FileSystem + ?Sized)) -> bool {
+        fs.metadata(self).is_ok()
+    }
+
+    /// Returns whether this path is a file in `fs`, following symlinks.
+    pub fn is_canonic_file_in(&self, fs: &(impl FileSystem + ?Sized)) -> bool {
+        fs.metadata(self).is_ok_and(|m| m.is_file())
+    }
+
+    /// Returns whether this path is a directory in `fs`, following symlinks.
+    pub fn is_canonic_dir_in(&self, fs: &(impl FileSystem + ?Sized)) -> bool {
+        fs.metadata(self).is_ok_and(|m| m.is_dir())
+    }
+
+    /// Returns whether this path is a symlink in `fs`.
+    pub fn is_canonic_symlink_in(&self, fs: &(impl FileSystem + ?Sized)) -> bool {
+        fs.symlink_metadata(self).is_ok_and(|m| m.is_symlink())
+    }
+
+    pub fn traverse_symlinks_in(
+        self,
+        fs: &(impl FileSystem + ?Sized),
+    ) -> Result<Self, &'static str> {
+        fs.canonicalize(&self)
+            .map_err(|_| "couldn't canonicalize path")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// ```text
+    /// /a/b/file.txt (3 bytes)
+    /// /a/link -> b
+    /// /a/up -> ../a/b/file.txt
+    /// /abs -> /a/b
+    /// /dangling -> /missing
+    /// /l1 -> l2
+    /// /l2 -> l1
+    /// C:\Users\me\
+    /// ```
+    fn fs() -> MemoryFs {
+        let p = |s: &str| Path::from_str(s).unwrap();
+
+        MemoryFs::new()
+            .with_file(p("/a/b/file.txt"), 3)
+            .and_then(|fs| fs.with_symlink(p("/a/link"), p("b")))
+            .and_then(|fs| fs.with_symlink(p("/a/up"), p("../a/b/file.txt")))
+            .and_then(|fs| fs.with_symlink(p("/abs"), p("/a/b")))
+            .and_then(|fs| fs.with_symlink(p("/dangling"), p("/missing")))
+            .and_then(|fs| fs.with_symlink(p("/l1"), p("l2")))
+            .and_then(|fs| fs.with_symlink(p("/l2"), p("l1")))
+            .and_then(|fs| fs.with_dir(p(r"C:\Users\me")))
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case("/a/b/file.txt", "/a/b/file.txt")]
+    #[case("/a/link/file.txt", "/a/b/file.txt")]
+    #[case("/a/link/", "/a/b/")]
+    #[case("/a/up", "/a/b/file.txt")]
+    #[case("/abs/./file.txt", "/a/b/file.txt")]
+    #[case("/a/link/../link/file.txt", "/a/b/file.txt")]
+    #[case("/abs/..", "/a/")]
+    #[case("/", "/")]
+    #[case(r"C:\Users\me\..", r"C:\Users\")]
+    fn canonicalize(#[case] path: &str, #[case] expected: &str) {
+        // act
+        let canonical = fs().canonicalize(&Path::from_str(path).unwrap()).unwrap();
+
+        // assert
+        assert_eq!(canonical, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case("/missing", FsError::NotFound("/missing".to_string()))]
+    #[case("/dangling", FsError::NotFound("/dangling".to_string()))]
+    #[case("/l1", FsError::Loop("/l1".to_string()))]
+    #[case("/a/b/file.txt/x", FsError::NotADirectory("/a/b/file.txt/x".to_string()))]
+    fn canonicalize_should_fail(#[case] path: &str, #[case] expected: FsError) {
+        // act
+        let canonical = fs().canonicalize(&Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(canonical, Err(expected));
+    }
+
+    #[rstest]
+    #[case("/", "the root of a memory filesystem cannot be replaced")]
+    #[case("/a/..", "the root of a memory filesystem cannot be replaced")]
+    #[case("a/b", "memory filesystems only hold absolute paths")]
+    #[case("~/a", "memory filesystems only hold absolute paths")]
+    fn with_file_should_fail(#[case] path: &str, #[case] expected: &str) {
+        // act
+        let fs = MemoryFs::new().with_file(Path::from_str(path).unwrap(), 0);
+
+        // assert
+        assert_eq!(fs.err(), Some(expected));
+    }
+
+    #[rstest]
+    #[case("/a/b/file.txt", true, true, false, false)]
+    #[case("/a/link", true, false, true, true)]
+    #[case("/a/up", true, true, false, true)]
+    #[case("/dangling", false, false, false, true)]
+    #[case("/l1", false, false, false, true)]
+    #[case("/missing", false, false, false, false)]
+    #[case(r"C:\Users", true, false, true, false)]
+    fn queries(
+        #[case] path: &str,
+        #[case] exists: bool,
+        #[case] is_file: bool,
+        #[case] is_dir: bool,
+        #[case] is_symlink: bool,
+    ) {
+        // arrange
+        let fs = fs();
+        let path = Path::from_str(path).unwrap();
+
+        // assert
+        assert_eq!(path.exists_in(&fs), exists);
+        assert_eq!(path.is_canonic_file_in(&fs), is_file);
+        assert_eq!(path.is_canonic_dir_in(&fs), is_dir);
+        assert_eq!(path.is_canonic_symlink_in(&fs), is_symlink);
+    }
+
+    #[rstest]
+    fn read_link_and_read_dir() {
+        // arrange
+        let fs = fs();
+
+        // act
+        let target = fs.read_link(&Path::from_str("/a/up").unwrap());
+        let not_a_link = fs.read_link(&Path::from_str("/a/b").unwrap());
+        let entries = fs.read_dir(&Path::from_str("/a/link").unwrap());
+        let metadata = fs.metadata(&Path::from_str("/a/up").unwrap());
+
+        // assert
+        assert_eq!(target, Ok(Path::from_str("../a/b/file.txt").unwrap()));
+        assert!(matches!(not_a_link, Err(FsError::NotASymlink(_))));
+        assert_eq!(entries, Ok(vec![DirEntry::new("file.txt", FileType::File)]));
+        assert_eq!(metadata, Ok(Metadata::new(FileType::File, 3)));
+    }
+
+    #[rstest]
+    #[case("/a/link/new/file.txt", "/a/b/new/file.txt")]
+    #[case("/dangling/x", "/missing/x")]
+    #[case("/a/link/..", "/a/")]
+    fn resolve_physical_in(#[case] path: &str, #[case] expected: &str) {
+        // act
+        let resolved = Path::from_str(path).unwrap().resolve_physical_in(&fs());
+
+        // assert
+        assert_eq!(resolved, Ok(Path::from_str(expected).unwrap()));
+    }
+
+    #[rstest]
+    #[case("/abs/..", Resolution::Physical, Ok("/a/"))]
+    #[case("/abs/..", Resolution::Logical, Ok("/"))]
+    #[case("link/../up", Resolution::Physical, Ok("/a/b/file.txt"))]
+    #[case("missing/../link/file.txt", Resolution::Physical, Ok("/a/b/file.txt"))]
+    #[case("~/link", Resolution::Physical, Ok("/a/b"))]
+    #[case("/l1", Resolution::Physical, Err(PhysicalError::Loop { segment: "l1".to_string(), limit: DEFAULT_SYMLINK_HOPS }))]
+    fn resolve_with_in(
+        #[case] path: &str,
+        #[case] resolution: Resolution,
+        #[case] expected: Result<&str, PhysicalError>,
+    ) {
+        // arrange
+        let env = MemoryEnv::new()
+            .with_current_dir(Path::from_str("/a").unwrap())
+            .with_home_dir(Path::from_str("/a").unwrap());
+
+        // act
+        let resolved = Path::from_str(path)
+            .unwrap()
+            .resolve_with_in(&env, resolution, &fs());
+
+        // assert
+        assert_eq!(resolved, expected.map(|e| Path::from_str(e).unwrap()));
+    }
+
+    #[rstest]
+    fn traverse_symlinks_in() {
+        // act
+        let resolved = Path::from_str("/abs/file.txt")
+            .unwrap()
+            .traverse_symlinks_in(&fs());
+
+        // assert
+        assert_eq!(resolved, Ok(Path::from_str("/a/b/file.txt").unwrap()));
+    }
+}
@@ -0,0 +1,319 @@
+//! Wrappers over the common [`std::fs`] operations which take canonic paths
+//! and report which path and operation failed.
+
+use std::{fmt, io};
+
+use crate::path::Path;
+
+/// An [`io::Error`] along with the operation and the path it failed on.
+#[derive(Debug)]
+pub struct Error(Box<ErrorInner>);
+
+#[derive(Debug)]
+struct ErrorInner {
+    operation: &'static str,
+    path: Path,
+    destination: Option<Path>,
+    source: io::Error,
+}
+
+impl Error {
+    fn new(operation: &'static str, path: &Path, source: io::Error) -> Self {
+        Error(Box::new(ErrorInner {
+            operation,
+            path: path.clone(),
+            destination: None,
+            source,
+        }))
+    }
+
+    fn with_destination(mut self, destination: &Path) -> Self {
+        self.0.destination = Some(destination.clone());
+        self
+    }
+
+    /// The name of the operation which failed, such as `read` or `rename`.
+    pub fn operation(&self) -> &'static str {
+        self.0.operation
+    }
+
+    /// The path the operation failed on. For `rename` and `copy` this is the
+    /// source.
+    pub fn path(&self) -> &Path {
+        &self.0.path
+    }
+
+    /// The destination of a failed `rename` or `copy`.
+    pub fn destination(&self) -> Option<&Path> {
+        self.0.destination.as_ref()
+    }
+
+    pub fn kind(&self) -> io::ErrorKind {
+        self.0.source.kind()
+    }
+
+    pub fn into_io_error(self) -> io::Error {
+        self.0.source
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.0.path.clone().builder().build_string();
+        write!(f, "failed to {} {path}", self.0.operation)?;
+
+        if let Some(destination) = &self.0.destination {
+            write!(f, " to {}", destination.clone().builder().build_string())?;
+        }
+
+        write!(f, ": {}", self.0.source)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0.source)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        io::Error::new(error.kind(), error)
+    }
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let path = path.as_ref();
+    std::fs::read(path.to_std_path()).map_err(|e| Error::new("read", path, e))
+}
+
+pub fn read_to_string(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    std::fs::read_to_string(path.to_std_path()).map_err(|e| Error::new("read", path, e))
+}
+
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+    let path = path.as_ref();
+    std::fs::write(path.to_std_path(), contents).map_err(|e| Error::new("write", path, e))
+}
+
+pub fn create_dir(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    std::fs::create_dir(path.to_std_path()).map_err(|e| Error::new("create directory", path, e))
+}
+
+pub fn create_dir_all(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    std::fs::create_dir_all(path.to_std_path()).map_err(|e| Error::new("create directory", path, e))
+}
+
+pub fn remove_file(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    std::fs::remove_file(path.to_std_path()).map_err(|e| Error::new("remove", path, e))
+}
+
+pub fn remove_dir(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    std::fs::remove_dir(path.to_std_path()).map_err(|e| Error::new("remove directory", path, e))
+}
+
+pub fn remove_dir_all(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    std::fs::remove_dir_all(path.to_std_path()).map_err(|e| Error::new("remove directory", path, e))
+}
+
+pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    std::fs::rename(from.to_std_path(), to.to_std_path())
+        .map_err(|e| Error::new("rename", from, e).with_destination(to))
+}
+
+/// Copies the contents of the file `from` to `to`, returning the number of
+/// bytes copied.
+pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<u64> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    std::fs::copy(from.to_std_path(), to.to_std_path())
+        .map_err(|e| Error::new("copy", from, e).with_destination(to))
+}
+
+pub fn metadata(path: impl AsRef<Path>) -> Result<std::fs::Metadata> {
+    let path = path.as_ref();
+    std::fs::metadata(path.to_std_path()).map_err(|e| Error::new("read metadata of", path, e))
+}
+
+pub fn symlink_metadata(path: impl AsRef<Path>) -> Result<std::fs::Metadata> {
+    let path = path.as_ref();
+    std::fs::symlink_metadata(path.to_std_path())
+        .map_err(|e| Error::new("read metadata of", path, e))
+}
+
+/// Creates a symlink at `link` pointing at `original`. On windows the link is a
+/// directory link if `original` is a directory.
+pub fn symlink(original: impl AsRef<Path>, link: impl AsRef<Path>) -> Result<()> {
+    let (original, link) = (original.as_ref(), link.as_ref());
+
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(original.to_std_path(), link.to_std_path());
+
+    #[cfg(windows)]
+    let result = if link
+        .parent()
+        .and_then(|parent| parent.join(original).ok())
+        .is_some_and(|target| target.is_canonic_dir())
+    {
+        std::os::windows::fs::symlink_dir(original.to_std_path(), link.to_std_path())
+    } else {
+        std::os::windows::fs::symlink_file(original.to_std_path(), link.to_std_path())
+    };
+
+    #[cfg(not(any(unix, windows)))]
+    let result = Err(io::Error::from(io::ErrorKind::Unsupported));
+
+    result.map_err(|e| Error::new("create symlink", link, e))
+}
+
+/// Lists the entries of the directory at `path`. Each entry is `path` joined
+/// with the entry's name, marked as a directory if it is one, following
+/// symlinks.
+pub fn read_dir(path: impl AsRef<Path>) -> Result<ReadDir> {
+    let path = path.as_ref();
+    let entries =
+        std::fs::read_dir(path.to_std_path()).map_err(|e| Error::new("read directory", path, e))?;
+
+    let mut dir = path.clone();
+    dir.is_dir = true;
+
+    Ok(ReadDir { dir, entries })
+}
+
+/// The entries of a directory, as returned by [`read_dir`].
+#[derive(Debug)]
+pub struct ReadDir {
+    dir: Path,
+    entries: std::fs::ReadDir,
+}
+
+impl Iterator for ReadDir {
+    type Item = Result<Path>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+
+        Some(
+            entry
+                .map_err(|e| Error::new("read directory", &self.dir, e))
+                .and_then(|entry| {
+                    let name = entry.file_name().into_string().map_err(|_| {
+                        let source = io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "file names must be valid UTF-8",
+                        );
+                        Error::new("read directory", &self.dir, source)
+                    })?;
+
+                    let mut path = self.dir.clone();
+                    path.segments.push(name);
+
+                    let file_type = entry
+                        .file_type()
+                        .map_err(|e| Error::new("read metadata of", &path, e))?;
+
+                    path.is_dir =
+                        file_type.is_dir() || file_type.is_symlink() && entry.path().is_dir();
+
+                    Ok(path)
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::test_support::TempTree;
+
+    fn child(dir: &Path, path: &str) -> Path {
+        dir.join(Path::from_str(path).unwrap()).unwrap()
+    }
+
+    #[rstest]
+    fn round_trip() {
+        // arrange
+        let tree = TempTree::new("fs");
+        let dir = tree.path();
+        let file = child(&dir, "a/b/file.txt");
+
+        // act
+        create_dir_all(file.parent().unwrap()).unwrap();
+        write(&file, "contents").unwrap();
+        let copied = copy(&file, child(&dir, "a/copy.txt")).unwrap();
+        rename(&file, child(&dir, "a/moved.txt")).unwrap();
+        let removed = remove_dir_all(child(&dir, "a/b"));
+
+        // assert
+        assert_eq!(copied, 8);
+        assert_eq!(
+            read_to_string(child(&dir, "a/copy.txt")).unwrap(),
+            "contents"
+        );
+        assert_eq!(read(child(&dir, "a/moved.txt")).unwrap(), b"contents");
+        assert!(removed.is_ok());
+        assert!(metadata(child(&dir, "a/b")).is_err());
+        assert!(!file.exists());
+    }
+
+    #[rstest]
+    fn read_dir_marks_directories() {
+        // arrange
+        let tree = TempTree::new("fs");
+        let dir = tree.path();
+        create_dir(child(&dir, "sub")).unwrap();
+        write(child(&dir, "file.txt"), "").unwrap();
+        #[cfg(unix)]
+        symlink(Path::from_str("sub").unwrap(), child(&dir, "link")).unwrap();
+
+        // act
+        let mut entries: Vec<Path> = read_dir(&dir).unwrap().map(|e| e.unwrap()).collect();
+        entries.sort_by_key(|e| e.basename().map(String::from));
+
+        // assert
+        let mut expected = vec![child(&dir, "file.txt"), child(&dir, "sub/")];
+        #[cfg(unix)]
+        expected.insert(1, child(&dir, "link/"));
+        assert_eq!(entries, expected);
+        assert!(entries.iter().all(|e| e.is_dir() == e.is_canonic_dir()));
+    }
+
+    #[rstest]
+    fn errors_name_the_path_and_operation() {
+        // arrange
+        let tree = TempTree::new("fs");
+        let dir = tree.path();
+        let missing = child(&dir, "missing.txt");
+
+        // act
+        let read_error = read(&missing).unwrap_err();
+        let rename_error = rename(&missing, child(&dir, "x.txt")).unwrap_err();
+
+        // assert
+        assert_eq!(read_error.operation(), "read");
+        assert_eq!(read_error.path(), &missing);
+        assert_eq!(read_error.kind(), io::ErrorKind::NotFound);
+        assert!(
+            read_error
+                .to_string()
+                .starts_with(&format!("failed to read {}: ", missing.clone().to_string()))
+        );
+        assert_eq!(rename_error.destination(), Some(&child(&dir, "x.txt")));
+        assert_eq!(
+            io::Error::from(rename_error).kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+}
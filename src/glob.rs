@@ -0,0 +1,454 @@
+//! Shell-style expansion of [`PathPattern`]s against a filesystem.
+
+use core::fmt;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    env::PathEnvironment,
+    filesystem::{FileSystem, FileType, FsError},
+    packed_list::PathSegmentList,
+    path::Path,
+    pattern::{LeadingDot, PathPattern, SegmentPattern},
+};
+
+/// How [`Path::glob_with`] expands a pattern.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GlobOptions {
+    follow_symlinks: bool,
+    hidden: bool,
+    strict: bool,
+}
+
+impl GlobOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets `**` descend into symlinks to directories. Symlinks back to a
+    /// directory being expanded are skipped.
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Lets wildcards and `**` match names starting with a `.`.
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Fails on directories and literal entries which can't be read, rather
+    /// than skipping them.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlobError {
+    /// The pattern couldn't be parsed or resolved.
+    Pattern(&'static str),
+    /// A directory or entry couldn't be read while expanding a strict glob.
+    Fs(FsError),
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlobError::Pattern(message) => f.write_str(message),
+            GlobError::Fs(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GlobError {}
+
+impl Path {
+    /// Expands `pattern` against the host filesystem, like a shell does.
+    ///
+    /// A leading `~` is expanded and `.` and `..` are resolved lexically before
+    /// the pattern is matched, except that a `..` after a wildcard segment is
+    /// kept, so `*/..` goes up from every match of `*`. Only the directories
+    /// the pattern can reach are read. The matches come back sorted, relative
+    /// if the pattern is relative.
+    #[cfg(feature = "std")]
+    pub fn glob(pattern: &str) -> Result<Vec<Path>, GlobError> {
+        Self::glob_with(pattern, GlobOptions::default())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn glob_with(pattern: &str, options: GlobOptions) -> Result<Vec<Path>, GlobError> {
+        Self::glob_in(
+            pattern,
+            options,
+            &crate::env::ProcessEnv,
+            &crate::filesystem::StdFs,
+        )
+    }
+
+    /// Expands `pattern` like [`Path::glob_with`], with `env` for `~` and the
+    /// current directory and against `fs`.
+    pub fn glob_in(
+        pattern: &str,
+        options: GlobOptions,
+        env: &impl PathEnvironment,
+        fs: &impl FileSystem,
+    ) -> Result<Vec<Path>, GlobError> {
+        let parsed: Path = pattern.parse().map_err(GlobError::Pattern)?;
+        let resolved = resolve_dots(
+            parsed
+                .expand_tilde(env, false)
+                .map_err(GlobError::Pattern)?,
+        );
+
+        let leading_dot = if options.hidden {
+            LeadingDot::Wildcard
+        } else {
+            LeadingDot::Explicit
+        };
+
+        let pattern = PathPattern::new(&resolved.rendered())
+            .map_err(GlobError::Pattern)?
+            .with_leading_dot(leading_dot);
+
+        let start = Path {
+            segments: PathSegmentList::new(),
+            stream: None,
+            is_dir: true,
+            ..resolved
+        };
+
+        let cwd = if start.is_absolute() {
+            None
+        } else {
+            Some(env.current_dir().map_err(GlobError::Pattern)?)
+        };
+
+        let mut expansion = Expansion {
+            pattern,
+            options,
+            fs,
+            cwd,
+            matches: Vec::new(),
+        };
+
+        expansion.expand(start, 0, &mut Vec::new())?;
+
+        let mut matches = expansion.matches;
+        matches.sort_by(|l, r| {
+            l.segments
+                .iter()
+                .map(|s| &s.0)
+                .cmp(r.segments.iter().map(|s| &s.0))
+        });
+        matches.dedup();
+        Ok(matches)
+    }
+}
+
+/// Drops `.` segments and collapses `..` into the literal segment before it.
+fn resolve_dots(path: Path) -> Path {
+    let mut segments: Vec<String> = Vec::new();
+
+    for segment in path.segments.iter() {
+        match segment.0.as_str() {
+            "." => {}
+            ".." if segments
+                .last()
+                .is_some_and(|last| last != ".." && !last.contains(['*', '?', '[', '{'])) =>
+            {
+                segments.pop();
+            }
+            name => segments.push(String::from(name)),
+        }
+    }
+
+    Path {
+        segments: segments.into_iter().collect(),
+        ..path
+    }
+}
+
+struct Expansion<'a, F> {
+    pattern: PathPattern,
+    options: GlobOptions,
+    fs: &'a F,
+    /// The directory relative patterns are expanded in.
+    cwd: Option<Path>,
+    matches: Vec<Path>,
+}
+
+impl<F: FileSystem> Expansion<'_, F> {
+    fn on_fs(&self, path: &Path) -> Result<Path, GlobError> {
+        match &self.cwd {
+            Some(cwd) => cwd.join(path).map_err(GlobError::Pattern),
+            None => Ok(path.clone()),
+        }
+    }
+
+    /// Matches `path` against the segment patterns from `index` on.
+    /// `ancestors` are the canonical paths of the symlinks `**` followed to get
+    /// here.
+    fn expand(
+        &mut self,
+        path: Path,
+        index: usize,
+        ancestors: &mut Vec<Path>,
+    ) -> Result<(), GlobError> {
+        if index == self.pattern.segments.len() {
+            if self.pattern.matches(&path) {
+                self.matches.push(path);
+            }
+
+            return Ok(());
+        }
+
+        if let SegmentPattern::Globstar = self.pattern.segments[index] {
+            self.expand(path.clone(), index + 1, ancestors)?;
+
+            for (child, is_symlink) in self.children(&path)? {
+                if !child.is_dir || self.pattern.is_hidden(child.basename().unwrap_or_default()) {
+                    continue;
+                }
+
+                if !is_symlink {
+                    self.expand(child, index, ancestors)?;
+                    continue;
+                }
+
+                if !self.options.follow_symlinks {
+                    continue;
+                }
+
+                let Ok(canonical) = self.fs.canonicalize(&self.on_fs(&child)?) else {
+                    continue;
+                };
+
+                if ancestors.contains(&canonical) || self.is_within(&canonical, &child)? {
+                    continue;
+                }
+
+                ancestors.push(canonical);
+                self.expand(child, index, ancestors)?;
+                ancestors.pop();
+            }
+
+            return Ok(());
+        }
+
+        if let Some(name) = self.pattern.literal(index) {
+            let mut child = path;
+            child.segments.push(name);
+
+            match self.fs.metadata(&self.on_fs(&child)?) {
+                Ok(metadata) => child.is_dir = metadata.is_dir(),
+                Err(FsError::NotFound(_) | FsError::NotADirectory(_)) => return Ok(()),
+                Err(e) if self.options.strict => return Err(GlobError::Fs(e)),
+                Err(_) => return Ok(()),
+            }
+
+            return self.expand(child, index + 1, ancestors);
+        }
+
+        for (child, _) in self.children(&path)? {
+            if self
+                .pattern
+                .matches_name(index, child.basename().unwrap_or_default())
+            {
+                self.expand(child, index + 1, ancestors)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the symlink at `link` points at one of its own
+    /// ancestors.
+    fn is_within(&self, canonical: &Path, link: &Path) -> Result<bool, GlobError> {
+        let mut parent = self.on_fs(link)?;
+
+        while let Some(next) = parent.parent() {
+            parent = next;
+
+            if self.fs.canonicalize(&parent).as_ref() == Ok(canonical) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// The entries of the directory at `path`, with `is_dir` following
+    /// symlinks, and whether each is a symlink.
+    fn children(&self, path: &Path) -> Result<Vec<(Path, bool)>, GlobError> {
+        let dir = self.on_fs(path)?;
+
+        let entries = match self.fs.read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(FsError::NotFound(_) | FsError::NotADirectory(_)) => return Ok(Vec::new()),
+            Err(e) if self.options.strict => return Err(GlobError::Fs(e)),
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let mut children = Vec::new();
+
+        for entry in entries {
+            let name = String::from(entry.name());
+            let mut child = path.clone();
+            child.segments.push(name.clone());
+
+            let mut on_fs = dir.clone();
+            on_fs.segments.push(name);
+
+            let is_symlink = entry.file_type() == FileType::Symlink;
+            child.is_dir = if is_symlink {
+                self.fs.metadata(&on_fs).is_ok_and(|m| m.is_dir())
+            } else {
+                entry.file_type() == FileType::Dir
+            };
+
+            children.push((child, is_symlink));
+        }
+
+        Ok(children)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use alloc::vec;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::{env::MemoryEnv, filesystem::MemoryFs};
+
+    /// ```text
+    /// /home/me/proj/Cargo.toml
+    /// /home/me/proj/a/Cargo.toml
+    /// /home/me/proj/a/src/lib.rs
+    /// /home/me/proj/.git/Cargo.toml
+    /// /home/me/proj/loop -> ..
+    /// /home/me/proj/linked -> /other
+    /// /other/Cargo.toml
+    /// ```
+    fn fs() -> MemoryFs {
+        let p = |s: &str| Path::from_str(s).unwrap();
+
+        MemoryFs::new()
+            .with_file(p("/home/me/proj/Cargo.toml"), 0)
+            .and_then(|fs| fs.with_file(p("/home/me/proj/a/Cargo.toml"), 0))
+            .and_then(|fs| fs.with_file(p("/home/me/proj/a/src/lib.rs"), 0))
+            .and_then(|fs| fs.with_file(p("/home/me/proj/.git/Cargo.toml"), 0))
+            .and_then(|fs| fs.with_symlink(p("/home/me/proj/loop"), p("..")))
+            .and_then(|fs| fs.with_symlink(p("/home/me/proj/linked"), p("/other")))
+            .and_then(|fs| fs.with_file(p("/other/Cargo.toml"), 0))
+            .unwrap()
+    }
+
+    fn env() -> MemoryEnv {
+        MemoryEnv::new()
+            .with_current_dir(Path::from_str("/home/me/proj").unwrap())
+            .with_home_dir(Path::from_str("/home/me").unwrap())
+    }
+
+    #[rstest]
+    #[case("~/proj/**/Cargo.toml", GlobOptions::new(), vec![
+        "/home/me/proj/Cargo.toml", "/home/me/proj/a/Cargo.toml",
+    ])]
+    #[case("~/proj/**/Cargo.toml", GlobOptions::new().with_hidden(true), vec![
+        "/home/me/proj/.git/Cargo.toml", "/home/me/proj/Cargo.toml", "/home/me/proj/a/Cargo.toml",
+    ])]
+    #[case("~/proj/**/Cargo.toml", GlobOptions::new().with_follow_symlinks(true), vec![
+        "/home/me/proj/Cargo.toml", "/home/me/proj/a/Cargo.toml", "/home/me/proj/linked/Cargo.toml",
+    ])]
+    #[case("**/*.rs", GlobOptions::new(), vec!["a/src/lib.rs"])]
+    #[case("*/", GlobOptions::new(), vec!["a/", "linked/", "loop/"])]
+    #[case("a/../*.toml", GlobOptions::new(), vec!["Cargo.toml"])]
+    #[case("/home/*/proj/a/src/*", GlobOptions::new(), vec!["/home/me/proj/a/src/lib.rs"])]
+    #[case("/missing/**", GlobOptions::new(), vec![])]
+    #[case("*/..", GlobOptions::new(), vec!["a/../", "linked/../", "loop/../"])]
+    #[case("a/**/../*.toml", GlobOptions::new(), vec!["a/../Cargo.toml", "a/src/../Cargo.toml"])]
+    #[case("./a/./src/../*.toml", GlobOptions::new(), vec!["a/Cargo.toml"])]
+    fn glob_in(#[case] pattern: &str, #[case] options: GlobOptions, #[case] expected: Vec<&str>) {
+        // act
+        let matches = Path::glob_in(pattern, options, &env(), &fs()).unwrap();
+
+        // assert
+        let expected: Vec<Path> = expected
+            .into_iter()
+            .map(|path| Path::from_str(path).unwrap())
+            .collect();
+        assert_eq!(matches, expected);
+    }
+
+    #[rstest]
+    fn glob_in_should_fail() {
+        // act
+        let matches = Path::glob_in("a[b", GlobOptions::new(), &env(), &fs());
+
+        // assert
+        assert_eq!(
+            matches,
+            Err(GlobError::Pattern("unclosed character class in pattern"))
+        );
+    }
+
+    #[rstest]
+    #[case(GlobOptions::new(), false)]
+    #[case(GlobOptions::new().with_strict(true), true)]
+    fn glob_in_literal_loop(#[case] options: GlobOptions, #[case] fails: bool) {
+        // arrange
+        let p = |s: &str| Path::from_str(s).unwrap();
+        let fs = fs()
+            .with_symlink(p("/home/me/proj/l1"), p("l2"))
+            .and_then(|fs| fs.with_symlink(p("/home/me/proj/l2"), p("l1")))
+            .unwrap();
+
+        // act
+        let matches = Path::glob_in("l1/*", options, &env(), &fs);
+
+        // assert
+        assert_eq!(
+            matches!(matches, Err(GlobError::Fs(FsError::Loop(_)))),
+            fails
+        );
+        assert_eq!(matches.is_ok(), !fails);
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[rstest]
+    fn glob_strict() {
+        use std::os::unix::fs::PermissionsExt;
+
+        use crate::test_support::TempTree;
+
+        // arrange
+        let tree = TempTree::new("glob")
+            .with_dir("locked")
+            .with_file("a.txt", "");
+        let root = tree.root();
+        std::fs::set_permissions(root.join("locked"), std::fs::Permissions::from_mode(0o000))
+            .unwrap();
+        let unreadable = std::fs::read_dir(root.join("locked")).is_err();
+        let pattern = alloc::format!("{}/**/*.txt", root.display());
+
+        // act
+        let lenient = Path::glob(&pattern);
+        let strict = Path::glob_with(&pattern, GlobOptions::new().with_strict(true));
+
+        // assert
+        assert_eq!(
+            lenient,
+            Ok(vec![Path::try_from(root.join("a.txt")).unwrap()])
+        );
+        assert_eq!(strict.is_err(), unreadable);
+
+        std::fs::set_permissions(root.join("locked"), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+    }
+}
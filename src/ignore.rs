@@ -0,0 +1,499 @@
+//! Matching paths against `.gitignore`, `.ignore` and `.dockerignore` files.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    path::Path,
+    pattern::{LeadingDot, PathPattern},
+    walk::Walker,
+};
+
+/// The rules an ignore file is read with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreDialect {
+    /// `.gitignore` and `.ignore`. Patterns without a `/` match at any depth,
+    /// a trailing `/` only matches directories, and nothing inside an ignored
+    /// directory can be re-included.
+    #[default]
+    Git,
+    /// `.dockerignore`. Every pattern is anchored to the file's directory,
+    /// trailing `/`s are dropped, and a pattern which matches a directory
+    /// matches everything in it unless a later `!` pattern re-includes it.
+    Docker,
+}
+
+/// Decides which paths are ignored by a set of ignore files.
+///
+/// Each file is attached to a directory relative to the matcher root and only
+/// applies beneath it. The last matching pattern wins, with the patterns of
+/// deeper files taking precedence over shallower ones.
+#[derive(Debug, Default, Clone)]
+pub struct IgnoreMatcher {
+    files: Vec<IgnoreFile>,
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreFile {
+    dir: Vec<String>,
+    dialect: IgnoreDialect,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: PathPattern,
+    negated: bool,
+}
+
+impl IgnoreMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the patterns in `contents`, as read from an ignore file in `dir`.
+    /// Lines which aren't valid patterns are skipped, as git does.
+    pub fn with_rules(
+        mut self,
+        dir: impl AsRef<Path>,
+        contents: &str,
+        dialect: IgnoreDialect,
+    ) -> Self {
+        let dir = dir
+            .as_ref()
+            .segments
+            .iter()
+            .map(|s| s.0.clone())
+            .filter(|s| s != ".")
+            .collect::<Vec<_>>();
+
+        let rules = contents
+            .lines()
+            .filter_map(|line| parse_rule(line, dialect))
+            .collect();
+
+        let index = self
+            .files
+            .partition_point(|file| file.dir.len() <= dir.len());
+        self.files.insert(
+            index,
+            IgnoreFile {
+                dir,
+                dialect,
+                rules,
+            },
+        );
+        self
+    }
+
+    /// Reads the ignore file at `file` and adds its patterns, as attached to
+    /// `dir`.
+    #[cfg(feature = "std")]
+    pub fn with_file(
+        self,
+        dir: impl AsRef<Path>,
+        file: impl AsRef<Path>,
+        dialect: IgnoreDialect,
+    ) -> Result<Self, crate::fs::Error> {
+        let contents = crate::fs::read_to_string(file)?;
+        Ok(self.with_rules(dir, &contents, dialect))
+    }
+
+    /// Reads every `.gitignore` and `.ignore` beneath `root`, skipping `.git`
+    /// and the directories they ignore.
+    #[cfg(feature = "std")]
+    pub fn discover(root: impl AsRef<Path>) -> Result<Self, crate::fs::Error> {
+        let mut root = root.as_ref().clone();
+        root.is_dir = true;
+
+        let mut matcher = IgnoreMatcher::new();
+        let mut pending = alloc::vec![Path::new()];
+
+        while let Some(relative) = pending.pop() {
+            let mut dir = root.clone();
+            for segment in relative.segments.iter() {
+                dir.segments.push(segment.0.clone());
+            }
+
+            for name in [".gitignore", ".ignore"] {
+                let mut file = dir.clone();
+                file.segments.push(name.to_string());
+                file.is_dir = false;
+
+                if crate::fs::metadata(&file).is_ok_and(|m| m.is_file()) {
+                    matcher = matcher.with_file(&relative, &file, IgnoreDialect::Git)?;
+                }
+            }
+
+            for entry in crate::fs::read_dir(&dir)? {
+                let entry = entry?;
+
+                if !entry.is_dir || entry.basename() == Some(".git") {
+                    continue;
+                }
+
+                let mut child = relative.clone();
+                child
+                    .segments
+                    .push(entry.basename().unwrap_or_default().to_string());
+                child.is_dir = true;
+
+                if !matcher.is_ignored(&child) {
+                    pending.push(child);
+                }
+            }
+        }
+
+        Ok(matcher)
+    }
+
+    /// Returns whether `path`, relative to the matcher root, is ignored. Its
+    /// `is_dir` flag decides whether directory-only patterns apply.
+    pub fn is_ignored(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        let segments: Vec<&str> = path
+            .segments
+            .iter()
+            .map(|s| s.0.as_str())
+            .filter(|s| *s != ".")
+            .collect();
+
+        // Git never looks inside an ignored directory, so nothing in one can
+        // be re-included.
+        for depth in 1..segments.len() {
+            if self.decide(&segments[..depth], true, IgnoreDialect::Git) == Some(true) {
+                return true;
+            }
+        }
+
+        self.files.iter().fold(None, |ignored, file| {
+            file.decide(&segments, path.is_dir()).or(ignored)
+        }) == Some(true)
+    }
+
+    fn decide(&self, segments: &[&str], is_dir: bool, dialect: IgnoreDialect) -> Option<bool> {
+        self.files
+            .iter()
+            .filter(|file| file.dialect == dialect)
+            .fold(None, |ignored, file| {
+                file.decide(segments, is_dir).or(ignored)
+            })
+    }
+}
+
+impl IgnoreFile {
+    /// Whether the last pattern of this file matching `segments` ignores it.
+    fn decide(&self, segments: &[&str], is_dir: bool) -> Option<bool> {
+        if segments.len() <= self.dir.len()
+            || self
+                .dir
+                .iter()
+                .zip(segments)
+                .any(|(dir, segment)| dir != segment)
+        {
+            return None;
+        }
+
+        let rest = &segments[self.dir.len()..];
+
+        let candidates: Vec<Path> = match self.dialect {
+            IgnoreDialect::Git => alloc::vec![relative_path(rest, is_dir)],
+            IgnoreDialect::Docker => (1..=rest.len())
+                .map(|len| relative_path(&rest[..len], len < rest.len() || is_dir))
+                .collect(),
+        };
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| candidates.iter().any(|path| rule.pattern.matches(path)))
+            .map(|rule| !rule.negated)
+    }
+}
+
+fn relative_path(segments: &[&str], is_dir: bool) -> Path {
+    let mut path = Path::new();
+
+    for segment in segments {
+        path.segments.push(segment.to_string());
+    }
+
+    path.is_dir = is_dir;
+    path
+}
+
+/// Parses a line of an ignore file into a rule, if it holds one.
+fn parse_rule(line: &str, dialect: IgnoreDialect) -> Option<Rule> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    let line = match dialect {
+        IgnoreDialect::Git => trim_unescaped_spaces(line),
+        IgnoreDialect::Docker => line.trim(),
+    };
+
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+
+    let (line, dir_only, anchored) = match dialect {
+        IgnoreDialect::Git => {
+            let (line, dir_only) = match line.strip_suffix('/') {
+                Some(line) => (line, true),
+                None => (line, false),
+            };
+
+            (line, dir_only, line.contains('/'))
+        }
+        IgnoreDialect::Docker => {
+            let line = line.trim();
+            let line = line.strip_prefix("./").unwrap_or(line);
+            (line.trim_end_matches('/'), false, true)
+        }
+    };
+
+    let line = line.trim_start_matches('/');
+
+    if line.is_empty() {
+        return None;
+    }
+
+    let mut segments = Vec::new();
+
+    if !anchored {
+        segments.push(String::from("**"));
+    }
+
+    // Escapes are handled while splitting, so `\\` is a literal backslash
+    // rather than a separator.
+    let mut segment = String::new();
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next() {
+                Some(escaped) => escaped,
+                None => break,
+            },
+            '/' => {
+                if !segment.is_empty() {
+                    segments.push(core::mem::take(&mut segment));
+                }
+                continue;
+            }
+            // Git has no braces, so they're matched literally.
+            '{' | '}' => {
+                segment.push('[');
+                segment.push(c);
+                segment.push(']');
+                continue;
+            }
+            c => {
+                segment.push(c);
+                continue;
+            }
+        };
+
+        if matches!(c, '*' | '?' | '[' | '{' | '}') {
+            segment.push('[');
+            segment.push(c);
+            segment.push(']');
+        } else {
+            segment.push(c);
+        }
+    }
+
+    if !segment.is_empty() {
+        segments.push(segment);
+    }
+
+    // `dir/**` matches what's inside `dir`, but not `dir` itself.
+    if segments.last().is_some_and(|last| last == "**") {
+        segments.push(String::from("*"));
+    }
+
+    let pattern = PathPattern::from_segments(&segments, dir_only)
+        .ok()?
+        .with_leading_dot(LeadingDot::Wildcard);
+
+    Some(Rule { pattern, negated })
+}
+
+/// Trims trailing spaces which aren't escaped with a `\`.
+fn trim_unescaped_spaces(line: &str) -> &str {
+    let mut end = line.len();
+
+    while line[..end].ends_with(' ') && !line[..end - 1].ends_with('\\') {
+        end -= 1;
+    }
+
+    &line[..end]
+}
+
+impl<F: crate::filesystem::FileSystem> Walker<F> {
+    /// Skips the entries `matcher` ignores, without descending into them. The
+    /// matcher root is the walk root.
+    pub fn with_ignore(self, matcher: IgnoreMatcher) -> Self {
+        self.with_prune(move |path| matcher.is_ignored(path))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use alloc::vec;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::filesystem::MemoryFs;
+
+    fn matcher(contents: &str, dialect: IgnoreDialect) -> IgnoreMatcher {
+        IgnoreMatcher::new().with_rules(Path::new(), contents, dialect)
+    }
+
+    #[rstest]
+    #[case("target", "target/", true)]
+    #[case("target", "a/b/target", true)]
+    #[case("target/", "a/target", false)]
+    #[case("target/", "a/target/", true)]
+    #[case("/target", "a/target/", false)]
+    #[case("/target", "target/", true)]
+    #[case("doc/*.md", "doc/a.md", true)]
+    #[case("doc/*.md", "src/doc/a.md", false)]
+    #[case("*.log\n!keep.log", "a/keep.log", false)]
+    #[case("*.log\n!keep.log", "a/other.log", true)]
+    #[case("logs/\n!logs/keep.log", "logs/keep.log", true)]
+    #[case("logs/**\n!logs/keep.log", "logs/keep.log", false)]
+    #[case("logs/**", "logs/", false)]
+    #[case("**/cache", "a/b/cache/", true)]
+    #[case("a/**/b", "a/x/y/b", true)]
+    #[case("# comment\n\\#file", "#file", true)]
+    #[case("\\!important", "!important", true)]
+    #[case("trailing\\ ", "trailing ", true)]
+    #[case("trailing   ", "trailing", true)]
+    #[case("\\*", "*", true)]
+    #[case("\\*", "a", false)]
+    #[case("{a,b}", "{a,b}", true)]
+    #[case("*", ".hidden", true)]
+    #[case("a\\\\b", "a/b", false)]
+    fn git(#[case] contents: &str, #[case] path: &str, #[case] expected: bool) {
+        // arrange
+        let matcher = matcher(contents, IgnoreDialect::Git);
+
+        // act
+        let ignored = matcher.is_ignored(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(ignored, expected);
+    }
+
+    #[rstest]
+    #[case("a\\\\b", true)]
+    #[case("a\\b", false)]
+    #[case("a*", true)]
+    fn git_backslash(#[case] contents: &str, #[case] expected: bool) {
+        // arrange
+        let matcher = matcher(contents, IgnoreDialect::Git);
+        let mut path = Path::new();
+        path.segments.push(String::from("a\\b"));
+
+        // act
+        let ignored = matcher.is_ignored(path);
+
+        // assert
+        assert_eq!(ignored, expected);
+    }
+
+    #[rstest]
+    #[case("target", "a/target", false)]
+    #[case("target", "target/debug/app", true)]
+    #[case("target/", "target", true)]
+    #[case("/target", "target", true)]
+    #[case("./target", "target", true)]
+    #[case("**/*.log", "a/b/c.log", true)]
+    #[case("logs\n!logs/keep.log", "logs/keep.log", false)]
+    #[case("logs\n!logs/keep.log", "logs/other.log", true)]
+    #[case("  spaced  ", "spaced", true)]
+    fn docker(#[case] contents: &str, #[case] path: &str, #[case] expected: bool) {
+        // arrange
+        let matcher = matcher(contents, IgnoreDialect::Docker);
+
+        // act
+        let ignored = matcher.is_ignored(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(ignored, expected);
+    }
+
+    #[rstest]
+    #[case("a/b.log", true)]
+    #[case("a/sub/b.log", false)]
+    #[case("a/sub/c.tmp", true)]
+    #[case("other/c.tmp", false)]
+    #[case("a/sub/", false)]
+    fn nested(#[case] path: &str, #[case] expected: bool) {
+        // arrange
+        let p = |s: &str| Path::from_str(s).unwrap();
+        let matcher = IgnoreMatcher::new()
+            .with_rules(p("a/sub/"), "!*.log\n*.tmp", IgnoreDialect::Git)
+            .with_rules(p("."), "*.log", IgnoreDialect::Git);
+
+        // act
+        let ignored = matcher.is_ignored(p(path));
+
+        // assert
+        assert_eq!(ignored, expected);
+    }
+
+    #[rstest]
+    fn walk_with_ignore() {
+        // arrange
+        let p = |s: &str| Path::from_str(s).unwrap();
+        let fs = MemoryFs::new()
+            .with_file(p("/r/src/lib.rs"), 0)
+            .and_then(|fs| fs.with_file(p("/r/target/debug/app"), 0))
+            .and_then(|fs| fs.with_file(p("/r/notes.log"), 0))
+            .unwrap();
+        let matcher = matcher("target/\n*.log", IgnoreDialect::Git);
+
+        // act
+        let entries: Vec<Path> = Walker::new_in(p("/r"), fs)
+            .with_sorted(true)
+            .with_ignore(matcher)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        // assert
+        assert_eq!(entries, vec![p("src/"), p("src/lib.rs")]);
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    fn discover() {
+        use crate::test_support::TempTree;
+
+        // arrange
+        let tree = TempTree::new("ignore")
+            .with_file(".gitignore", "ignored/\n")
+            .with_file("a/.ignore", "*.tmp\n")
+            .with_file("a/ignored/.gitignore", "!*.tmp\n");
+
+        // act
+        let matcher = IgnoreMatcher::discover(tree.path()).unwrap();
+
+        // assert
+        let p = |s: &str| Path::from_str(s).unwrap();
+        assert!(matcher.is_ignored(p("a/x.tmp")));
+        assert!(matcher.is_ignored(p("a/ignored/x.tmp")));
+        assert!(!matcher.is_ignored(p("x.tmp")));
+        assert_eq!(matcher.files.len(), 2);
+    }
+}
@@ -3,10 +3,34 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+pub mod alias;
 pub mod builder;
+pub mod compare;
+pub mod confine;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod dir;
+pub mod env;
+mod file_url;
+pub mod filesystem;
+#[cfg(feature = "std")]
+pub mod fs;
+pub mod glob;
+pub mod ignore;
+pub mod msys;
 mod packed_list;
 mod parser;
 pub mod path;
+pub mod pattern;
+pub mod physical;
+pub mod remap;
+pub mod session;
+pub mod template;
+#[cfg(all(test, feature = "std"))]
+mod test_support;
+pub mod users;
+pub mod walk;
+pub mod windows;
+pub mod wsl;
 mod zip_greedy;
 
 #[cfg(feature = "std")]
@@ -0,0 +1,345 @@
+use alloc::string::ToString;
+
+use crate::{
+    packed_list::PathSegmentList,
+    path::{Drive, Path, Prefix, Root},
+    windows::{drive_mount_path, mounted_drive_path},
+};
+
+/// The two ways POSIX layers on windows spell a drive: MSYS2 and Git Bash use
+/// `/c/Users`, while Cygwin uses `/cygdrive/c/Users`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MsysForm {
+    Msys,
+    Cygwin,
+}
+
+/// Where a POSIX layer mounts windows drives and where its own root lives, used
+/// to translate paths the way `cygpath` does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsysConfig {
+    cygdrive_prefix: Path,
+    root_mount: Path,
+}
+
+impl MsysConfig {
+    /// The MSYS2 defaults: drives under `/` and the root at `C:\msys64`.
+    pub fn msys2() -> Self {
+        MsysConfig {
+            cygdrive_prefix: root(&[]),
+            root_mount: drive_root('C', &["msys64"]),
+        }
+    }
+
+    /// The Cygwin defaults: drives under `/cygdrive` and the root at
+    /// `C:\cygwin64`.
+    pub fn cygwin() -> Self {
+        MsysConfig {
+            cygdrive_prefix: root(&["cygdrive"]),
+            root_mount: drive_root('C', &["cygwin64"]),
+        }
+    }
+
+    /// Sets the directory drives are mounted under, as configured by the
+    /// `cygdrive` entry in `/etc/fstab`.
+    pub fn with_cygdrive_prefix(mut self, prefix: impl AsRef<Path>) -> Result<Self, &'static str> {
+        let prefix = prefix.as_ref();
+
+        if prefix.prefix.is_some() || prefix.drive.is_some() || prefix.root != Some(Root::Normal) {
+            return Err("the cygdrive prefix must be an absolute unix path");
+        }
+
+        self.cygdrive_prefix = prefix.clone();
+        self.cygdrive_prefix.is_dir = true;
+        Ok(self)
+    }
+
+    /// Sets the windows directory which `/` is mounted on.
+    pub fn with_root_mount(mut self, root_mount: impl AsRef<Path>) -> Result<Self, &'static str> {
+        let root_mount = root_mount.as_ref();
+
+        if root_mount.prefix.is_some()
+            || root_mount.drive.is_none()
+            || root_mount.root != Some(Root::Normal)
+        {
+            return Err("the root mount must be a drive-rooted windows path");
+        }
+
+        self.root_mount = root_mount.clone();
+        self.root_mount.is_dir = true;
+        Ok(self)
+    }
+
+    pub fn cygdrive_prefix(&self) -> &Path {
+        &self.cygdrive_prefix
+    }
+
+    pub fn root_mount(&self) -> &Path {
+        &self.root_mount
+    }
+}
+
+impl Path {
+    /// Detects whether this is a drive path under the cygdrive prefix of
+    /// `config`: in MSYS form (`/c/Users`) when drives are mounted at `/`, and
+    /// in Cygwin form (`/cygdrive/c/Users`) when they're mounted deeper.
+    pub fn msys_form(&self, config: &MsysConfig) -> Option<MsysForm> {
+        if self.prefix.is_some() || self.drive.is_some() || self.root != Some(Root::Normal) {
+            return None;
+        }
+
+        mounted_drive_path(self, &config.cygdrive_prefix)?;
+
+        if config.cygdrive_prefix.segments.len() == 0 {
+            Some(MsysForm::Msys)
+        } else {
+            Some(MsysForm::Cygwin)
+        }
+    }
+
+    /// Translates a path from an MSYS or Cygwin shell into a native windows path.
+    ///
+    /// Paths under the cygdrive prefix become drive paths, so `/c/Users` becomes
+    /// `C:\Users`, and every other absolute path is placed under the root mount,
+    /// so `/usr/bin` becomes `C:\msys64\usr\bin`. Relative, UNC and drive paths
+    /// are understood by both sides and are returned unchanged.
+    pub fn msys_to_windows(&self, config: &MsysConfig) -> Result<Self, &'static str> {
+        if self.prefix.is_some() {
+            return Err("device and extended-length paths are not msys paths");
+        }
+
+        if self.is_relative() || self.drive.is_some() || self.root == Some(Root::Unc) {
+            return Ok(self.clone());
+        }
+
+        if let Some(path) = mounted_drive_path(self, &config.cygdrive_prefix) {
+            return Ok(path);
+        }
+
+        let mut result = config.root_mount.clone();
+
+        for segment in self.segments.iter() {
+            result.segments.push(segment.clone());
+        }
+
+        result.stream = self.stream.clone();
+        result.is_dir = self.is_dir();
+        Ok(result)
+    }
+
+    /// Translates a native windows path into the path an MSYS or Cygwin shell
+    /// sees.
+    ///
+    /// Paths under the root mount are made relative to `/`, so
+    /// `C:\msys64\usr\bin` becomes `/usr/bin`, and other drive paths are placed
+    /// under the cygdrive prefix, so `C:\Users` becomes `/c/Users`. Relative and
+    /// UNC paths are understood by both sides and are returned unchanged.
+    pub fn windows_to_msys(&self, config: &MsysConfig) -> Result<Self, &'static str> {
+        match (&self.prefix, &self.drive, &self.root) {
+            (Some(Prefix::Device), _, _) => Err("device paths have no msys equivalent"),
+            (_, Some(Drive { letter }), Some(Root::Normal)) => {
+                if let Some(rest) = strip_root_mount(self, &config.root_mount, *letter) {
+                    return Ok(Path {
+                        root: Some(Root::Normal),
+                        segments: rest,
+                        stream: self.stream.clone(),
+                        is_dir: self.is_dir(),
+                        ..Path::new()
+                    });
+                }
+
+                Ok(drive_mount_path(self, *letter, &config.cygdrive_prefix))
+            }
+            (_, None, Some(Root::Unc)) => {
+                let mut result = self.clone();
+                result.prefix = None;
+                Ok(result)
+            }
+            (Some(Prefix::ExtendedPath), _, _) => {
+                Err("extended-length paths must be drive or unc paths")
+            }
+            (None, None, None) => Ok(self.clone()),
+            (None, _, _) => {
+                Err("drive-relative and rooted windows paths must be made absolute first")
+            }
+        }
+    }
+}
+
+fn strip_root_mount(path: &Path, root_mount: &Path, letter: char) -> Option<PathSegmentList> {
+    let mount_letter = root_mount.drive.as_ref()?.letter;
+
+    if !mount_letter.eq_ignore_ascii_case(&letter) {
+        return None;
+    }
+
+    let mut segments = path.segments.iter();
+
+    for mount_segment in root_mount.segments.iter() {
+        if !segments.next()?.0.eq_ignore_ascii_case(&mount_segment.0) {
+            return None;
+        }
+    }
+
+    Some(segments.cloned().collect())
+}
+
+fn root(segments: &[&str]) -> Path {
+    Path {
+        root: Some(Root::Normal),
+        segments: segments.iter().map(|s| s.to_string()).collect(),
+        is_dir: true,
+        ..Path::new()
+    }
+}
+
+fn drive_root(letter: char, segments: &[&str]) -> Path {
+    Path {
+        drive: Some(Drive { letter }),
+        ..root(segments)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(MsysConfig::msys2(), "/c/Users/me", Some(MsysForm::Msys))]
+    #[case(MsysConfig::msys2(), "/c", Some(MsysForm::Msys))]
+    #[case(MsysConfig::msys2(), "/cygdrive/c/Users/me", None)]
+    #[case(MsysConfig::cygwin(), "/cygdrive/c/Users/me", Some(MsysForm::Cygwin))]
+    #[case(MsysConfig::cygwin(), "/cygdrive/d", Some(MsysForm::Cygwin))]
+    #[case(MsysConfig::cygwin(), "/cygdrive", None)]
+    #[case(MsysConfig::cygwin(), "/c/Users/me", None)]
+    #[case(MsysConfig::cygwin().with_cygdrive_prefix(Path::from_str("/drives").unwrap()).unwrap(), "/drives/e/x", Some(MsysForm::Cygwin))]
+    #[case(MsysConfig::cygwin().with_cygdrive_prefix(Path::from_str("/").unwrap()).unwrap(), "/e/x", Some(MsysForm::Msys))]
+    #[case(MsysConfig::msys2(), "/usr/bin", None)]
+    #[case(MsysConfig::msys2(), "c/Users", None)]
+    #[case(MsysConfig::msys2(), r"C:\Users", None)]
+    #[case(MsysConfig::msys2(), r"\\server\c", None)]
+    fn msys_form(
+        #[case] config: MsysConfig,
+        #[case] path: &str,
+        #[case] expected: Option<MsysForm>,
+    ) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let form = path.msys_form(&config);
+
+        // assert
+        assert_eq!(form, expected);
+    }
+
+    #[rstest]
+    #[case(MsysConfig::msys2(), "/c/Users/me", r"C:\Users\me")]
+    #[case(MsysConfig::msys2(), "/d/", r"D:\")]
+    #[case(MsysConfig::msys2(), "/usr/bin/bash", r"C:\msys64\usr\bin\bash")]
+    #[case(MsysConfig::msys2(), "/", r"C:\msys64\")]
+    #[case(MsysConfig::msys2(), "/cygdrive/c/x", r"C:\msys64\cygdrive\c\x")]
+    #[case(MsysConfig::cygwin(), "/cygdrive/c/Users/me", r"C:\Users\me")]
+    #[case(MsysConfig::cygwin(), "/c/Users/me", r"C:\cygwin64\c\Users\me")]
+    #[case(MsysConfig::cygwin(), "/etc/fstab", r"C:\cygwin64\etc\fstab")]
+    #[case(MsysConfig::msys2(), "src/main.rs", "src/main.rs")]
+    #[case(MsysConfig::msys2(), "//server/share/x", "//server/share/x")]
+    #[case(MsysConfig::msys2(), "C:/Users", "C:/Users")]
+    fn msys_to_windows(#[case] config: MsysConfig, #[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.msys_to_windows(&config).unwrap();
+
+        // assert
+        assert_eq!(result, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case(MsysConfig::msys2(), r"C:\Users\me", "/c/Users/me")]
+    #[case(MsysConfig::msys2(), r"D:\", "/d/")]
+    #[case(MsysConfig::msys2(), r"C:\msys64\usr\bin", "/usr/bin")]
+    #[case(MsysConfig::msys2(), r"c:\MSYS64\usr\bin", "/usr/bin")]
+    #[case(MsysConfig::msys2(), r"C:\msys64", "/")]
+    #[case(MsysConfig::msys2(), r"C:\msys640\x", "/c/msys640/x")]
+    #[case(MsysConfig::msys2(), r"\\?\C:\x", "/c/x")]
+    #[case(MsysConfig::cygwin(), r"C:\Users\me", "/cygdrive/c/Users/me")]
+    #[case(MsysConfig::cygwin(), r"C:\cygwin64\etc", "/etc")]
+    #[case(MsysConfig::msys2(), r"\\server\share\x", "//server/share/x")]
+    #[case(MsysConfig::msys2(), r"src\main.rs", "src/main.rs")]
+    fn windows_to_msys(#[case] config: MsysConfig, #[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.windows_to_msys(&config).unwrap();
+
+        // assert
+        assert_eq!(result, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case(r"C:foo")]
+    #[case(r"\foo")]
+    #[case(r"\\.\COM1")]
+    fn windows_to_msys_should_fail(#[case] path: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.windows_to_msys(&MsysConfig::msys2());
+
+        // assert
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    fn custom_prefix_and_root_mount() {
+        // arrange
+        let config = MsysConfig::msys2()
+            .with_cygdrive_prefix(Path::from_str("/mnt").unwrap())
+            .unwrap()
+            .with_root_mount(Path::from_str(r"D:\tools\git").unwrap())
+            .unwrap();
+
+        // act
+        let drive = Path::from_str("/mnt/e/x").unwrap().msys_to_windows(&config);
+        let root = Path::from_str("/usr/bin").unwrap().msys_to_windows(&config);
+        let back = Path::from_str(r"D:\tools\git\etc")
+            .unwrap()
+            .windows_to_msys(&config);
+
+        // assert
+        assert_eq!(drive.unwrap(), Path::from_str(r"E:\x").unwrap());
+        assert_eq!(
+            root.unwrap(),
+            Path::from_str(r"D:\tools\git\usr\bin").unwrap()
+        );
+        assert_eq!(back.unwrap(), Path::from_str("/etc").unwrap());
+    }
+
+    #[rstest]
+    fn convert_between_msys_and_cygwin() {
+        // arrange
+        let path = Path::from_str("/c/Users/me").unwrap();
+
+        // act
+        let cygwin = path
+            .msys_to_windows(&MsysConfig::msys2())
+            .unwrap()
+            .windows_to_msys(&MsysConfig::cygwin())
+            .unwrap();
+
+        // assert
+        assert_eq!(cygwin, Path::from_str("/cygdrive/c/Users/me").unwrap());
+        assert_eq!(
+            cygwin.msys_form(&MsysConfig::cygwin()),
+            Some(MsysForm::Cygwin)
+        );
+    }
+}
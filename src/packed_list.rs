@@ -40,9 +40,17 @@ impl PathSegmentList {
         node.next.and_then(|next_index| self.nodes.get(next_index))
     }
 
+    pub(crate) fn tail(&self) -> Option<&Node> {
+        self.tail.and_then(|index| self.nodes.get(index))
+    }
+
+    pub(crate) fn prev(&self, node: &Node) -> Option<&Node> {
+        node.prev.and_then(|prev_index| self.nodes.get(prev_index))
+    }
+
     pub(crate) fn free(&mut self, index: usize) -> bool {
         if index < self.nodes.len() {
-            // self.nodes[index] = Node::default();
+            self.nodes[index] = Node::default();
             self.free_list.push(index);
             true
         } else {
@@ -58,14 +66,16 @@ impl PathSegmentList {
             self.nodes.len()
         };
 
+        let node = Node {
+            value,
+            prev: None,
+            next: None,
+        };
+
         if index >= self.nodes.len() {
-            self.nodes.push(Node {
-                value,
-                prev: None,
-                next: None,
-            });
+            self.nodes.push(node);
         } else {
-            self.nodes[index].value = value;
+            self.nodes[index] = node;
         }
 
         if self.head.is_none() {
@@ -118,10 +128,12 @@ impl PathSegmentList {
         }
     }
 
-    pub fn iter(&self) -> PathSegmentListIter {
+    pub fn iter(&self) -> PathSegmentListIter<'_> {
         PathSegmentListIter {
             list: self,
-            current: self.head(),
+            front: self.head(),
+            back: self.tail(),
+            remaining: self.len(),
         }
     }
 }
@@ -190,30 +202,54 @@ impl Iterator for PathSegmentListIntoIter {
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct PathSegmentListIter<'a> {
     list: &'a PathSegmentList,
-    current: Option<&'a Node>,
+    front: Option<&'a Node>,
+    back: Option<&'a Node>,
+    remaining: usize,
 }
 
 impl<'a> Iterator for PathSegmentListIter<'a> {
     type Item = &'a PathSegment;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let current = self.current?;
-        self.current = self.list.next(current);
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let current = self.front?;
+        self.front = self.list.next(current);
+        self.remaining -= 1;
         Some(&current.value)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
+impl DoubleEndedIterator for PathSegmentListIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let current = self.back?;
+        self.back = self.list.prev(current);
+        self.remaining -= 1;
+        Some(&current.value)
+    }
+}
+
+impl ExactSizeIterator for PathSegmentListIter<'_> {}
+
 impl<'a> IntoIterator for &'a PathSegmentList {
     type Item = &'a PathSegment;
     type IntoIter = PathSegmentListIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        PathSegmentListIter {
-            list: self,
-            current: self.head(),
-        }
+        self.iter()
     }
 }
 
@@ -296,4 +332,49 @@ mod test {
         assert_eq!(second.value.0, "c");
         assert!(packed_list.next(second).is_none());
     }
+
+    #[rstest]
+    fn push_after_free() {
+        // arrange
+        let mut packed_list = ["a", "b", "c"]
+            .into_iter()
+            .map(|s| PathSegment(s.to_string()))
+            .collect::<PathSegmentList>();
+        packed_list[0].next = None;
+        packed_list.head = Some(2);
+        packed_list[2].prev = None;
+        packed_list.free(0);
+        packed_list.free(1);
+
+        // act
+        packed_list.push(PathSegment("d".to_string()));
+
+        // assert
+        let values = packed_list.iter().map(|s| s.0.as_str()).collect::<Vec<_>>();
+        assert_eq!(values, ["c", "d"]);
+        assert_eq!(packed_list.tail().unwrap().next, None);
+        assert_eq!(packed_list.head().unwrap().prev, None);
+    }
+
+    #[rstest]
+    fn iter_double_ended() {
+        // arrange
+        let mut packed_list = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|s| PathSegment(s.to_string()))
+            .collect::<PathSegmentList>();
+        packed_list.remove(1);
+
+        // act
+        let mut iter = packed_list.iter();
+
+        // assert
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back().map(|s| s.0.as_str()), Some("d"));
+        assert_eq!(iter.next().map(|s| s.0.as_str()), Some("a"));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next_back().map(|s| s.0.as_str()), Some("c"));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
 }
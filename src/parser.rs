@@ -2,25 +2,36 @@ use alloc::{string::String, vec::Vec};
 
 use crate::{
     packed_list::PathSegmentList,
-    path::{Drive, Path, Prefix, Root},
+    path::{Drive, Path, Prefix, Root, Stream},
 };
 
+/// Parses a path which may be either a Unix or a Windows path. Streams are
+/// only parsed when a prefix, drive or UNC root makes it a Windows path, since
+/// on Unix `a:b.txt` is a file name.
 pub fn parse_path(input: &str) -> Result<Path, &'static str> {
-    parse(Cursor::new(input.chars().collect()))
+    parse(Cursor::new(input.chars().collect()), false)
 }
 
-fn parse(mut cursor: Cursor) -> Result<Path, &'static str> {
+/// Parses a Windows path, where a `:` in the last segment always starts a
+/// stream.
+pub fn parse_windows_path(input: &str) -> Result<Path, &'static str> {
+    parse(Cursor::new(input.chars().collect()), true)
+}
+
+fn parse(mut cursor: Cursor, windows: bool) -> Result<Path, &'static str> {
     let prefix = parse_prefix(&mut cursor);
     let drive = parse_drive(&mut cursor);
     let root = parse_root(&mut cursor, &prefix, drive.is_some())?;
 
-    let (segments, is_dir) = parse_segments(&mut cursor)?;
+    let streams = windows || prefix.is_some() || drive.is_some() || root == Some(Root::Unc);
+    let (segments, stream, is_dir) = parse_segments(&mut cursor, streams)?;
 
     Ok(Path {
         prefix,
         drive,
         root,
         segments,
+        stream,
         is_dir,
     })
 }
@@ -56,7 +67,7 @@ fn parse_drive(cursor: &mut Cursor) -> Option<Drive> {
         (Some(letter), Some(':')) if letter.is_alphabetic() => {
             cursor.eat();
             cursor.eat();
-            Some(Drive { letter: letter })
+            Some(Drive { letter })
         }
         _ => None,
     }
@@ -111,7 +122,10 @@ fn parse_root(
     Ok(None)
 }
 
-fn parse_segments(cursor: &mut Cursor) -> Result<(PathSegmentList, bool), &'static str> {
+fn parse_segments(
+    cursor: &mut Cursor,
+    streams: bool,
+) -> Result<(PathSegmentList, Option<Stream>, bool), &'static str> {
     let mut segments = Vec::new();
 
     let mut last = '\0';
@@ -134,8 +148,35 @@ fn parse_segments(cursor: &mut Cursor) -> Result<(PathSegmentList, bool), &'stat
         segments.push(segment);
     }
 
-    let is_dir = last == '/' || last == '.';
-    Ok((segments.into_iter().collect(), is_dir))
+    let mut stream = None;
+
+    if streams
+        && last != '/'
+        && let Some(segment) = segments.last_mut()
+    {
+        stream = parse_stream(segment);
+    }
+
+    let is_dir = stream.is_none() && (last == '/' || last == '.');
+    Ok((segments.into_iter().collect(), stream, is_dir))
+}
+
+/// Splits an alternate data stream off the final segment, e.g. `file.txt:name:$DATA`.
+/// Segments which don't form a well-formed stream reference are left untouched.
+fn parse_stream(segment: &mut String) -> Option<Stream> {
+    let (base, rest) = segment.split_once(':')?;
+
+    if base.is_empty() {
+        return None;
+    }
+
+    let stream = match rest.split_once(':') {
+        Some((name, stream_type)) => Stream::new(name, Some(stream_type)).ok()?,
+        None => Stream::new(rest, None).ok()?,
+    };
+
+    segment.truncate(base.len());
+    Some(stream)
 }
 
 #[derive(Debug, Clone)]
@@ -154,7 +195,7 @@ impl Cursor {
     }
 
     pub fn eat(&mut self) -> Option<char> {
-        if self.chars.len() > 0 {
+        if !self.chars.is_empty() {
             Some(self.chars.remove(0))
         } else {
             None
@@ -8,11 +8,15 @@ use alloc::{
 };
 
 use crate::{
-    builder::{Base, StringPathBuilder},
-    packed_list::{Node, PathSegmentList},
+    builder::{Base, DefaultEnv, StringPathBuilder},
+    env::PathEnvironment,
+    packed_list::{Node, PathSegmentList, PathSegmentListIter},
     parser,
+    windows::{self, WindowsCwdState},
     zip_greedy::zip_greedy,
 };
+#[cfg(feature = "std")]
+use crate::{env::ProcessEnv, filesystem::StdFs};
 
 #[derive(Debug, Clone, Eq)]
 pub struct Path {
@@ -20,6 +24,7 @@ pub struct Path {
     pub(crate) drive: Option<Drive>,
     pub(crate) root: Option<Root>,
     pub(crate) segments: PathSegmentList,
+    pub(crate) stream: Option<Stream>,
     pub(crate) is_dir: bool,
 }
 
@@ -29,6 +34,7 @@ impl PartialEq for Path {
             && self.drive == other.drive
             && self.root == other.root
             && self.segments == other.segments
+            && self.stream == other.stream
             && self.is_dir() == other.is_dir()
     }
 }
@@ -40,6 +46,7 @@ impl Path {
             prefix: None,
             drive: None,
             root: None,
+            stream: None,
             is_dir: false,
         }
     }
@@ -80,10 +87,8 @@ impl Path {
                 Some(Drive {
                     letter: path_letter,
                 }),
-            ) => {
-                if *self_letter != *path_letter {
-                    return Err("cannot join two paths from different drives");
-                }
+            ) if *self_letter != *path_letter => {
+                return Err("cannot join two paths from different drives");
             }
             (None, Some(path_drive)) => result.drive = Some(path_drive.clone()),
             _ => {}
@@ -100,6 +105,10 @@ impl Path {
             self.is_dir
         };
 
+        if path.segments.len() > 0 {
+            result.stream = path.stream.take();
+        }
+
         for segment in path.segments.into_iter() {
             result.segments.push(segment);
         }
@@ -111,10 +120,10 @@ impl Path {
         let basename = basename.as_ref();
         let mut result = self.clone().resolve()?;
 
-        if result.segments.remove_last().is_some() {
-            if result.segments.len() > 0 {
-                result.is_dir = true;
-            }
+        result.stream = None;
+
+        if result.segments.remove_last().is_some() && result.segments.len() > 0 {
+            result.is_dir = true;
         }
 
         let path = Path::from_str(basename)?.resolve()?;
@@ -127,19 +136,19 @@ impl Path {
         } else {
             let mut clone = self.clone();
             clone.segments = PathSegmentList::new();
+            clone.stream = None;
             Some(clone)
         }
     }
 
-    pub fn dirname(&self) -> Option<&str> {
-        let mut components = self.components();
-        components.pop()?;
-        components.pop()
+    /// Returns the directory containing the path, like [`Path::parent`].
+    pub fn dirname(&self) -> Option<Path> {
+        self.ancestors().next()
     }
 
     #[cfg(feature = "std")]
     pub fn exists(&self) -> bool {
-        self.to_std_path().exists()
+        self.exists_in(&StdFs)
     }
 
     pub fn is_absolute(&self) -> bool {
@@ -160,17 +169,17 @@ impl Path {
 
     #[cfg(feature = "std")]
     pub fn is_canonic_file(&self) -> bool {
-        self.to_std_path().is_file()
+        self.is_canonic_file_in(&StdFs)
     }
 
     #[cfg(feature = "std")]
     pub fn is_canonic_dir(&self) -> bool {
-        self.to_std_path().is_dir()
+        self.is_canonic_dir_in(&StdFs)
     }
 
     #[cfg(feature = "std")]
     pub fn is_canonic_symlink(&self) -> bool {
-        self.to_std_path().is_symlink()
+        self.is_canonic_symlink_in(&StdFs)
     }
 
     pub fn is_root(&self) -> bool {
@@ -178,7 +187,7 @@ impl Path {
     }
 
     pub fn basename(&self) -> Option<&str> {
-        self.components().pop()
+        self.segments().next_back()
     }
 
     pub fn stem(&self) -> Option<&str> {
@@ -199,8 +208,11 @@ impl Path {
 
     #[cfg(feature = "std")]
     pub fn with_cwd_base(&self) -> Result<Self, &'static str> {
-        let cwd = std::env::current_dir().map_err(|_| "failed to get cwd")?;
-        Path::try_from(cwd)?.join(self)
+        self.with_cwd_base_in(&ProcessEnv)
+    }
+
+    pub fn with_cwd_base_in(&self, env: &impl PathEnvironment) -> Result<Self, &'static str> {
+        env.current_dir()?.join(self)
     }
 
     pub fn extension(&self) -> Option<&str> {
@@ -219,13 +231,82 @@ impl Path {
         Some(&basename[last + 1..])
     }
 
+    /// Parses `s` as a Windows path, so that a `:` in the last segment starts
+    /// a stream even in a relative path like `notes.txt:Zone.Identifier`.
+    /// [`Path::from_str`] only does so for paths with a prefix, drive or UNC
+    /// root.
+    pub fn parse_windows(s: &str) -> Result<Self, &'static str> {
+        parser::parse_windows_path(s)
+    }
+
+    pub fn stream(&self) -> Option<&Stream> {
+        self.stream.as_ref()
+    }
+
+    pub fn with_stream(
+        &self,
+        name: impl AsRef<str>,
+        stream_type: Option<&str>,
+    ) -> Result<Self, &'static str> {
+        if self.segments.len() == 0 {
+            return Err("alternate data streams must be attached to a segment");
+        }
+
+        let mut result = self.clone();
+        result.stream = Some(Stream::new(name.as_ref(), stream_type)?);
+        result.is_dir = false;
+        Ok(result)
+    }
+
     pub fn components(&self) -> Vec<&str> {
-        self.segments.iter().map(|p| p.0.as_str()).collect()
+        self.segments().collect()
+    }
+
+    /// Returns an iterator over the segments, from the first to the last.
+    pub fn segments(&self) -> Segments<'_> {
+        Segments {
+            inner: self.segments.iter(),
+        }
+    }
+
+    /// Returns the segment at `index`.
+    pub fn segment(&self, index: usize) -> Option<&str> {
+        self.segments().nth(index)
+    }
+
+    /// Returns the number of segments.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns whether the path has no segments. This counts segments only,
+    /// so `/` and `C:` are empty even though they name a directory.
+    pub fn is_empty(&self) -> bool {
+        self.segments.len() == 0
+    }
+
+    /// Returns an iterator over the parents of the path, from its
+    /// [`Path::parent`] up to its root, or `.` for a relative path. The server
+    /// and share of a UNC path are part of its root. It stops before removing
+    /// a `..`, since what that leads up to isn't known.
+    pub fn ancestors(&self) -> Ancestors {
+        let fixed = if self.root == Some(Root::Unc) { 2 } else { 0 };
+
+        Ancestors {
+            path: self.clone(),
+            remaining: self
+                .segments()
+                .skip(fixed)
+                .rev()
+                .take_while(|s| *s != "..")
+                .count(),
+        }
     }
 
     pub fn parent(&self) -> Option<Path> {
         let mut parent = self.clone();
         parent.segments.remove_last()?;
+        parent.stream = None;
         parent.is_dir = true;
 
         if !parent.has_root() && parent.segments.len() == 0 {
@@ -235,32 +316,114 @@ impl Path {
         Some(parent)
     }
 
-    pub fn resolve(mut self) -> Result<Self, &'static str> {
-        let Some(head_index) = self.segments.head else {
-            return Ok(self);
-        };
-
-        if let Some(Node {
-            value: PathSegment(s),
-            ..
-        }) = self.segments.head()
+    pub fn resolve(self) -> Result<Self, &'static str> {
+        #[cfg(feature = "std")]
+        return self.resolve_with(&DefaultEnv::default(), Resolution::Lexical);
+
+        // Without std there's no home directory to expand `~` to.
+        #[cfg(not(feature = "std"))]
+        return self.resolve_keeping_tilde(&DefaultEnv::default(), Resolution::Lexical);
+    }
+
+    /// Resolves the path with the given [`Resolution`], expanding a leading `~`
+    /// or `~user` segment with the home directories `env` knows of first. A
+    /// `~user` whose home can't be found is left as it is, the way shells do,
+    /// but a `~` without a home is an error.
+    pub fn resolve_with(
+        self,
+        env: &impl PathEnvironment,
+        resolution: Resolution,
+    ) -> Result<Self, &'static str> {
+        self.resolve_with_home(env, resolution, false)
+    }
+
+    /// [`Path::resolve_with`], but a `~` without a home is left as it is.
+    pub fn resolve_keeping_tilde(
+        self,
+        env: &impl PathEnvironment,
+        resolution: Resolution,
+    ) -> Result<Self, &'static str> {
+        self.resolve_with_home(env, resolution, true)
+    }
+
+    /// Expands a leading `~` or `~user` segment with the home directories `env`
+    /// knows of. The segment is only special in a relative path, so `/~/x` is
+    /// left as it is.
+    pub(crate) fn expand_tilde(
+        mut self,
+        env: &impl PathEnvironment,
+        keep_tilde: bool,
+    ) -> Result<Self, &'static str> {
+        if !is_anchored(&self.prefix, &self.drive, &self.root)
+            && let Some(Node {
+                value: PathSegment(s),
+                ..
+            }) = self.segments.head()
+            && let Some(user) = s.strip_prefix('~')
         {
-            if s == "~" {
-                #[cfg(feature = "std")]
-                {
-                    let home = dirs::home_dir().ok_or_else(|| "couldn't resolve home")?;
-                    let path = Path::from_str(
-                        home.to_str()
-                            .expect("home must be valid on its own operating system"),
-                    )?;
-
-                    let head = self.segments.head.expect("head exists as a ~");
-                    self.segments.remove(head);
-                    self = path.join(self)?;
-                }
+            let home = if user.is_empty() && !keep_tilde {
+                Some(env.home_dir().ok_or("couldn't resolve home")?)
+            } else if user.is_empty() {
+                env.home_dir()
+            } else {
+                env.user_home_dir(user)
+            };
+
+            if let Some(home) = home {
+                let head = self.segments.head.expect("head exists as a ~");
+                self.segments.remove(head);
+                self = home.join(self)?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    fn resolve_with_home(
+        mut self,
+        env: &impl PathEnvironment,
+        resolution: Resolution,
+        keep_tilde: bool,
+    ) -> Result<Self, &'static str> {
+        self = self.expand_tilde(env, keep_tilde)?;
+
+        match resolution {
+            Resolution::Lexical => {}
+            Resolution::Logical if self.is_relative() => self = self.with_cwd_base_in(env)?,
+            Resolution::Logical => {}
+            #[cfg(feature = "std")]
+            Resolution::Physical => {
+                return self
+                    .resolve_physical_at(env, &crate::filesystem::StdFs)
+                    .map_err(|e| e.message());
+            }
+            #[cfg(not(feature = "std"))]
+            Resolution::Physical => {
+                return Err("physical resolution needs a filesystem, see Path::resolve_with_in");
             }
         }
 
+        self = self.resolve_lexically();
+
+        if resolution == Resolution::Logical {
+            while let Some(head) = self.segments.head
+                && self.segments[head].value.0 == ".."
+            {
+                self.segments.remove(head);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Removes `.` segments and `..` segments along with the segment before
+    /// them, without expanding `~` or looking at the environment. A leading
+    /// `..` is kept.
+    pub(crate) fn resolve_lexically(mut self) -> Self {
+        let Some(head_index) = self.segments.head else {
+            return self;
+        };
+
         fn traverse(mut path: PathSegmentList, index: usize) -> PathSegmentList {
             let node = &path[index];
             let value = &node.value;
@@ -315,7 +478,35 @@ impl Path {
         }
 
         self.segments = traverse(self.segments, head_index);
-        Ok(self)
+        self
+    }
+
+    /// Replaces a leading `home` with `~`, so `/home/me/x` becomes `~/x`. Paths
+    /// outside of `home` are returned unchanged.
+    pub fn contract_home_at(&self, home: impl AsRef<Path>) -> Self {
+        let Some(rest) = self.strip_prefix(home) else {
+            return self.clone();
+        };
+
+        let mut contracted = Path::from("~".to_string());
+
+        for segment in rest.segments.iter() {
+            contracted.segments.push(segment.clone());
+        }
+
+        contracted.stream = rest.stream;
+        contracted.is_dir = rest.is_dir;
+        contracted
+    }
+
+    #[cfg(feature = "std")]
+    pub fn contract_home(&self) -> Result<Self, &'static str> {
+        self.contract_home_in(&ProcessEnv)
+    }
+
+    pub fn contract_home_in(&self, env: &impl PathEnvironment) -> Result<Self, &'static str> {
+        let home = env.home_dir().ok_or("couldn't resolve home")?;
+        Ok(self.contract_home_at(home))
     }
 
     pub fn resolve_at(&self, base: impl AsRef<Path>) -> Result<Self, &'static str> {
@@ -324,20 +515,32 @@ impl Path {
 
     #[cfg(feature = "std")]
     pub fn resolve_at_cwd(&self) -> Result<Self, &'static str> {
-        self.with_cwd_base()?.resolve()
+        self.resolve_at_cwd_in(&ProcessEnv)
+    }
+
+    pub fn resolve_at_cwd_in(&self, env: &impl PathEnvironment) -> Result<Self, &'static str> {
+        self.with_cwd_base_in(env)?
+            .resolve_with(env, Resolution::Lexical)
     }
 
     #[cfg(feature = "std")]
     pub fn traverse_symlinks(self) -> Result<Self, &'static str> {
-        let path = Into::<std::path::PathBuf>::into(self)
-            .canonicalize()
-            .map_err(|_| "couldn't canonicalize path")?;
+        self.traverse_symlinks_in(&StdFs)
+    }
 
-        TryFrom::<std::path::PathBuf>::try_from(path).map_err(|_| "hello")
+    /// Computes the fully qualified path Win32 `GetFullPathNameW` would produce for
+    /// this path, using `state` for the working directories. See
+    /// [`windows::full_path_name`] for the rules.
+    pub fn win32_full_path(&self, state: &WindowsCwdState) -> Result<Self, &'static str> {
+        windows::full_path(self, state)
     }
 
     pub fn is_windows_compatible(&self) -> bool {
         self.segments.iter().all(|s| s.is_windows_compatible())
+            && self
+                .stream
+                .as_ref()
+                .is_none_or(|s| s.is_windows_compatible())
     }
 
     pub fn is_unix_compatible(&self) -> bool {
@@ -351,10 +554,23 @@ impl Path {
         self.into()
     }
 
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(self) -> String {
         self.builder().build_string()
     }
 
+    /// Renders the path the way it would be written, keeping the trailing
+    /// separator of a directory.
+    pub(crate) fn rendered(&self) -> String {
+        let mut rendered = self.clone().builder().build_string();
+
+        if self.is_dir && self.segments.len() > 0 {
+            rendered.push('/');
+        }
+
+        rendered
+    }
+
     #[cfg(feature = "std")]
     pub fn to_os_string(self) -> OsString {
         self.builder().build_os_string()
@@ -383,6 +599,7 @@ impl Path {
                 drive: self.drive.clone(),
                 root: None,
                 segments: PathSegmentList::new(),
+                stream: None,
                 is_dir: false,
             });
         };
@@ -391,17 +608,13 @@ impl Path {
             return None;
         }
 
-        if let (Some(PathSegment(l)), Some(PathSegment(r))) = (l, r) {
-            if l == ".." || r == ".." {
-                return None;
-            }
+        if let (Some(PathSegment(l)), Some(PathSegment(r))) = (l, r)
+            && (l == ".." || r == "..")
+        {
+            return None;
         }
 
-        loop {
-            let Some((l, r)) = zipped.peek() else {
-                break;
-            };
-
+        while let Some((l, r)) = zipped.peek() {
             if l != r {
                 break;
             }
@@ -431,6 +644,12 @@ impl Path {
             segments.push("..".to_string());
         }
 
+        let stream = if li.iter().flatten().count() > 0 {
+            self.stream.clone()
+        } else {
+            None
+        };
+
         for s in li.iter().flatten() {
             segments.push((*s).clone());
         }
@@ -440,11 +659,22 @@ impl Path {
             drive: self.drive.clone(),
             root: None,
             segments,
+            stream,
             is_dir,
         })
     }
 }
 
+/// Returns whether a path or pattern with these components is anchored to a
+/// root, drive or prefix.
+pub(crate) fn is_anchored(
+    prefix: &Option<Prefix>,
+    drive: &Option<Drive>,
+    root: &Option<Root>,
+) -> bool {
+    prefix.is_some() || drive.is_some() || root.is_some()
+}
+
 impl FromStr for Path {
     type Err = &'static str;
 
@@ -479,6 +709,7 @@ impl From<PathSegmentList> for Path {
             prefix: None,
             drive: None,
             root: None,
+            stream: None,
             is_dir: false,
         }
     }
@@ -491,6 +722,7 @@ impl<P: Into<PathSegment>> From<P> for Path {
             prefix: None,
             drive: None,
             root: None,
+            stream: None,
             is_dir: false,
         }
     }
@@ -511,13 +743,13 @@ impl AsRef<Path> for Path {
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub(crate) struct PathSegment(pub(crate) String);
 
+pub(crate) const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
 impl PathSegment {
     pub fn is_windows_compatible(&self) -> bool {
-        const RESERVED_NAMES: [&str; 22] = [
-            "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7",
-            "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
-        ];
-
         let segment = self.0.as_str();
 
         if segment.is_empty() {
@@ -534,10 +766,10 @@ impl PathSegment {
             }
         }
 
-        if let Some(last) = segment.chars().rev().next() {
-            if last == '.' || last == ' ' {
-                return false;
-            }
+        if let Some(last) = segment.chars().next_back()
+            && (last == '.' || last == ' ')
+        {
+            return false;
         }
 
         let name_end = segment.find('.').unwrap_or(segment.len());
@@ -602,6 +834,136 @@ impl FromStr for PathSegment {
     }
 }
 
+/// How `..` segments are evaluated when resolving a path.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// `..` removes the previous segment, and relative paths stay relative.
+    #[default]
+    Lexical,
+    /// The path is made absolute against the current directory and `..` then
+    /// removes the previous segment, never climbing above the root. This is
+    /// what `cd -L` does in a shell.
+    Logical,
+    /// The path is made absolute against the current directory and resolved
+    /// with [`Path::resolve_physical`], so `..` applies to the target of any
+    /// symlink before it. This is what `cd -P` and the kernel do.
+    ///
+    /// Without std, there's no host filesystem to resolve against, so
+    /// [`Path::resolve_with_in`] must be given one.
+    Physical,
+}
+
+/// The segments of a [`Path`], as returned by [`Path::segments`].
+#[derive(Clone)]
+pub struct Segments<'a> {
+    inner: PathSegmentListIter<'a>,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|segment| segment.0.as_str())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Segments<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|segment| segment.0.as_str())
+    }
+}
+
+impl ExactSizeIterator for Segments<'_> {}
+
+/// The parents of a [`Path`], as returned by [`Path::ancestors`].
+#[derive(Clone)]
+pub struct Ancestors {
+    /// The last ancestor yielded, or the path itself at first.
+    path: Path,
+    remaining: usize,
+}
+
+impl Iterator for Ancestors {
+    type Item = Path;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+
+        self.path.segments.remove_last();
+        self.path.stream = None;
+        self.path.is_dir = true;
+
+        let mut ancestor = self.path.clone();
+
+        if !ancestor.has_root() && ancestor.segments.len() == 0 {
+            ancestor.segments.push(".".to_string());
+        }
+
+        Some(ancestor)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Ancestors {}
+
+/// An NTFS alternate data stream attached to the final segment of a path, as in
+/// `file.txt:stream:$DATA`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stream {
+    pub(crate) name: String,
+    pub(crate) stream_type: Option<String>,
+}
+
+impl Stream {
+    pub fn new(name: impl Into<String>, stream_type: Option<&str>) -> Result<Self, &'static str> {
+        let name = name.into();
+
+        if name.contains(['/', '\\', ':']) {
+            return Err("stream names cannot contain separators or colons");
+        }
+
+        if let Some(stream_type) = stream_type {
+            if !stream_type.starts_with('$') || stream_type.len() == 1 {
+                return Err("stream types must start with '$'");
+            }
+
+            if stream_type.contains(['/', '\\', ':']) {
+                return Err("stream types cannot contain separators or colons");
+            }
+        } else if name.is_empty() {
+            return Err("streams must have a name or a type");
+        }
+
+        Ok(Stream {
+            name,
+            stream_type: stream_type.map(|t| t.to_string()),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn stream_type(&self) -> Option<&str> {
+        self.stream_type.as_deref()
+    }
+
+    pub fn is_windows_compatible(&self) -> bool {
+        self.name.chars().all(|c| c != '\0')
+            && self.stream_type.as_ref().is_none_or(|t| {
+                t.chars()
+                    .all(|c| c.is_ascii_uppercase() || c == '$' || c == '_')
+            })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Prefix {
     ExtendedPath,
@@ -623,9 +985,12 @@ pub(crate) enum Root {
 mod test {
     use core::str::FromStr;
 
+    use alloc::vec;
+
     use rstest::rstest;
 
     use super::*;
+    use crate::env::MemoryEnv;
 
     #[rstest]
     #[case("a/b/c", "d/e", "a/b/c/d/e")]
@@ -713,15 +1078,85 @@ mod test {
     }
 
     #[rstest]
-    fn dirname() {
+    #[case("a/b/c", Some("a/b/"))]
+    #[case("a/b/c/", Some("a/b/"))]
+    #[case("/a/b", Some("/a/"))]
+    #[case("a", Some("./"))]
+    #[case("/", None)]
+    #[case("C:/a/file.txt:stream", Some("C:/a/"))]
+    fn dirname(#[case] path: &str, #[case] expected: Option<&str>) {
         // arrange
-        let path = Path::from_str("a/b/c").unwrap();
+        let path = Path::from_str(path).unwrap();
 
         // act
         let dirname = path.dirname();
 
         // assert
-        assert_eq!(dirname, Some("b"));
+        assert_eq!(dirname, expected.map(|e| Path::from_str(e).unwrap()));
+    }
+
+    #[rstest]
+    #[case("a/b/c", vec!["a", "b", "c"])]
+    #[case("/a/b/", vec!["a", "b"])]
+    #[case("/", vec![])]
+    #[case(r"\\Server\Share\foo", vec!["Server", "Share", "foo"])]
+    #[case("C:/Users/me", vec!["Users", "me"])]
+    fn segments(#[case] path: &str, #[case] expected: Vec<&str>) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let segments = path.segments();
+
+        // assert
+        assert_eq!(segments.len(), expected.len());
+        assert_eq!(path.len(), expected.len());
+        assert_eq!(path.is_empty(), expected.is_empty());
+        assert_eq!(segments.clone().collect::<Vec<_>>(), expected);
+        assert!(segments.rev().eq(expected.iter().rev().copied()));
+    }
+
+    #[rstest]
+    #[case("a/b/c", 0, Some("a"))]
+    #[case("a/b/c", 2, Some("c"))]
+    #[case("a/b/c", 3, None)]
+    #[case("/", 0, None)]
+    fn segment(#[case] path: &str, #[case] index: usize, #[case] expected: Option<&str>) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let segment = path.segment(index);
+
+        // assert
+        assert_eq!(segment, expected);
+    }
+
+    #[rstest]
+    #[case("/a/b/c", vec!["/a/b/", "/a/", "/"])]
+    #[case("a/b", vec!["a/", "./"])]
+    #[case("C:/a/b.txt:s", vec!["C:/a/", "C:/"])]
+    #[case(r"\\Server\Share\foo", vec![r"\\Server\Share\"])]
+    #[case(r"\\Server\Share\a\b", vec![r"\\Server\Share\a\", r"\\Server\Share\"])]
+    #[case(r"\\Server\Share\", vec![])]
+    #[case(r"\\?\UNC\Server\Share\foo", vec![r"\\?\UNC\Server\Share\"])]
+    #[case("/", vec![])]
+    #[case("../a", vec!["../"])]
+    #[case("a/../b/c", vec!["a/../b/", "a/../"])]
+    #[case("..", vec![])]
+    fn ancestors(#[case] path: &str, #[case] expected: Vec<&str>) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let ancestors = path.ancestors().collect::<Vec<_>>();
+
+        // assert
+        let expected = expected
+            .into_iter()
+            .map(|e| Path::from_str(e).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ancestors, expected);
     }
 
     #[rstest]
@@ -1018,9 +1453,16 @@ mod test {
     #[case("a?", false)]
     #[case("a*", false)]
     #[case("a\0", false)]
+    #[case("C:/notes.txt:Zone.Identifier", true)]
+    #[case("C:/file.txt:stream:$DATA", true)]
+    #[case("C:/file.txt::$DATA", true)]
+    #[case("C:/file.txt:a:$data", false)]
+    #[case("C:/dir:stream/file.txt", false)]
+    #[case("notes.txt:Zone.Identifier", true)]
+    #[case(r"dir\file.txt::$DATA", true)]
     fn is_windows_compatible(#[case] path: &str, #[case] expected: bool) {
         // arrange
-        let path = Path::from_str(path).unwrap();
+        let path = Path::parse_windows(path).unwrap();
 
         // act
         let compatible = path.is_windows_compatible();
@@ -1029,6 +1471,140 @@ mod test {
         assert_eq!(compatible, expected);
     }
 
+    #[rstest]
+    #[case("C:/notes.txt:Zone.Identifier", "notes.txt", Some(("Zone.Identifier", None)))]
+    #[case("C:/file.txt:stream:$DATA", "file.txt", Some(("stream", Some("$DATA"))))]
+    #[case("C:/file.txt::$DATA", "file.txt", Some(("", Some("$DATA"))))]
+    #[case(r"C:\dir\file.txt:s", "file.txt", Some(("s", None)))]
+    #[case("c:file.txt:s", "file.txt", Some(("s", None)))]
+    #[case(r"\\Server\Share\file.txt:s", "file.txt", Some(("s", None)))]
+    #[case(r"\\?\C:\file.txt:s:$DATA", "file.txt", Some(("s", Some("$DATA"))))]
+    #[case("C:/file.txt:", "file.txt:", None)]
+    #[case("C:/:stream", ":stream", None)]
+    #[case("C:/file.txt:a:DATA", "file.txt:a:DATA", None)]
+    #[case("C:/file.txt:a:b:$DATA", "file.txt:a:b:$DATA", None)]
+    #[case("C:/dir:stream/", "dir:stream", None)]
+    #[case("C:/file.txt", "file.txt", None)]
+    #[case("notes.txt:Zone.Identifier", "notes.txt:Zone.Identifier", None)]
+    #[case("/home/me/ab:c.txt", "ab:c.txt", None)]
+    #[case("dir/file.txt::$DATA", "file.txt::$DATA", None)]
+    fn stream(
+        #[case] path: &str,
+        #[case] basename: &str,
+        #[case] expected: Option<(&str, Option<&str>)>,
+    ) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let stream = path.stream();
+
+        // assert
+        assert_eq!(path.basename(), Some(basename));
+        assert_eq!(stream.map(|s| (s.name(), s.stream_type())), expected);
+    }
+
+    #[rstest]
+    #[case("notes.txt:Zone.Identifier", "notes.txt", Some(("Zone.Identifier", None)))]
+    #[case(r"dir\file.txt::$DATA", "file.txt", Some(("", Some("$DATA"))))]
+    #[case("/home/me/ab:c.txt", "ab", Some(("c.txt", None)))]
+    #[case("dir:stream/", "dir:stream", None)]
+    fn parse_windows(
+        #[case] path: &str,
+        #[case] basename: &str,
+        #[case] expected: Option<(&str, Option<&str>)>,
+    ) {
+        // act
+        let path = Path::parse_windows(path).unwrap();
+
+        // assert
+        assert_eq!(path.basename(), Some(basename));
+        assert_eq!(path.stream().map(|s| (s.name(), s.stream_type())), expected);
+    }
+
+    #[rstest]
+    #[case("C:/notes.txt:Zone.Identifier", Some("txt"), Some("notes"))]
+    #[case("C:/archive.tar.gz:meta:$DATA", Some("gz"), Some("archive.tar"))]
+    #[case("C:/Makefile:s", None, Some("Makefile"))]
+    fn stream_is_ignored_by_extension_and_stem(
+        #[case] path: &str,
+        #[case] extension: Option<&str>,
+        #[case] stem: Option<&str>,
+    ) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let (actual_extension, actual_stem) = (path.extension(), path.stem());
+
+        // assert
+        assert_eq!(actual_extension, extension);
+        assert_eq!(actual_stem, stem);
+    }
+
+    #[rstest]
+    #[case(
+        "C:/a/notes.txt",
+        "Zone.Identifier",
+        None,
+        "C:/a/notes.txt:Zone.Identifier"
+    )]
+    #[case("C:/a/notes.txt:old", "new", Some("$DATA"), "C:/a/notes.txt:new:$DATA")]
+    #[case("C:file.txt", "", Some("$DATA"), "C:file.txt::$DATA")]
+    fn with_stream(
+        #[case] path: &str,
+        #[case] name: &str,
+        #[case] stream_type: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.with_stream(name, stream_type).unwrap();
+
+        // assert
+        assert_eq!(result, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case("", "a", None)]
+    #[case("/", "a", None)]
+    #[case("file.txt", "a:b", None)]
+    #[case("file.txt", "a", Some("DATA"))]
+    #[case("file.txt", "", None)]
+    fn with_stream_should_fail(
+        #[case] path: &str,
+        #[case] name: &str,
+        #[case] stream_type: Option<&str>,
+    ) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.with_stream(name, stream_type);
+
+        // assert
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case("C:/a/notes.txt:b", "c", "C:/a/c")]
+    #[case("C:/a/b", "C:c.txt:s", "C:/a/b/c.txt:s")]
+    #[case("C:/a/b:s", "", "C:/a/b:s")]
+    fn join_stream(#[case] left: &str, #[case] right: &str, #[case] expected: &str) {
+        // arrange
+        let path1 = Path::from_str(left).unwrap();
+        let path2 = Path::from_str(right).unwrap();
+
+        // act
+        let joined_path = path1.join(path2).unwrap();
+
+        // assert
+        let expected = Path::from_str(expected).unwrap();
+        assert_eq!(joined_path.stream(), expected.stream());
+    }
+
     #[rstest]
     #[case("a", true)]
     #[case(".a", true)]
@@ -1298,6 +1874,119 @@ mod test {
         assert_eq!(path.segments.len(), len);
     }
 
+    #[rstest]
+    #[case("~alice/projects", "/home/alice/projects")]
+    #[case("~alice/projects/../notes/", "/home/alice/notes/")]
+    #[case("~alice", "/home/alice")]
+    #[case(r"~bob\src", r"D:\bob\src")]
+    #[case("~carol/projects", "~carol/projects")]
+    #[case("a/~alice/b", "a/~alice/b")]
+    #[case("~/x/../y", "/home/me/y")]
+    #[case("/~alice/x", "/~alice/x")]
+    #[case("/~/x", "/~/x")]
+    #[case("C:/~/x", "C:/~/x")]
+    #[case("C:~/x", "C:~/x")]
+    fn resolve_with(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let env = MemoryEnv::new()
+            .with_home_dir(Path::from_str("/home/me").unwrap())
+            .with_user("alice", Path::from_str("/home/alice").unwrap())
+            .with_user("bob", Path::from_str(r"D:\bob").unwrap());
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let resolved = path.resolve_with(&env, Resolution::Lexical).unwrap();
+
+        // assert
+        assert_eq!(resolved, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case("~", Err("couldn't resolve home"))]
+    #[case("~/x", Err("couldn't resolve home"))]
+    #[case("~alice/x", Ok("~alice/x"))]
+    #[case("x/~", Ok("x/~"))]
+    fn resolve_with_no_home(#[case] path: &str, #[case] expected: Result<&str, &str>) {
+        // arrange
+        let env = MemoryEnv::new();
+        let p = Path::from_str(path).unwrap();
+
+        // act
+        let resolved = p.clone().resolve_with(&env, Resolution::Lexical);
+        let kept = p.resolve_keeping_tilde(&env, Resolution::Lexical);
+
+        // assert
+        assert_eq!(resolved, expected.map(|e| Path::from_str(e).unwrap()));
+        assert_eq!(kept, Ok(Path::from_str(path).unwrap()));
+    }
+
+    #[rstest]
+    #[case("a/b/../c", Resolution::Lexical, "a/c")]
+    #[case("../x", Resolution::Lexical, "../x")]
+    #[case("/..", Resolution::Lexical, "/..")]
+    #[case("a/b/../c", Resolution::Logical, "/work/app/a/c")]
+    #[case("../../../x", Resolution::Logical, "/x")]
+    #[case("/..", Resolution::Logical, "/")]
+    #[case("/x/../y", Resolution::Logical, "/y")]
+    #[case(r"C:\x\..\y", Resolution::Logical, r"C:\y")]
+    #[case("~/x/..", Resolution::Logical, "/home/me/")]
+    fn resolve_with_resolution(
+        #[case] path: &str,
+        #[case] resolution: Resolution,
+        #[case] expected: &str,
+    ) {
+        // arrange
+        let env = MemoryEnv::new()
+            .with_current_dir(Path::from_str("/work/app").unwrap())
+            .with_home_dir(Path::from_str("/home/me").unwrap());
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let resolved = path.resolve_with(&env, resolution).unwrap();
+
+        // assert
+        assert_eq!(resolved, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    fn resolve_after_freeing_segments() {
+        // arrange
+        let path = Path::from_str("/a/b/c/../..").unwrap().resolve().unwrap();
+
+        // act
+        let resolved = path
+            .join(Path::from_str("d/../e").unwrap())
+            .unwrap()
+            .resolve();
+
+        // assert
+        assert_eq!(resolved, Ok(Path::from_str("/a/e").unwrap()));
+    }
+
+    #[rstest]
+    #[case("/home/me/x", "/home/me", "~/x")]
+    #[case("/home/me/a/b/", "/home/me/", "~/a/b/")]
+    #[case("/home/me", "/home/me", "~")]
+    #[case("/home/me/", "/home/me", "~/")]
+    #[case("/home/me/notes.txt:s", "/home/me", "~/notes.txt:s")]
+    #[case(r"c:\Users\me\x", r"C:\Users\me", "~/x")]
+    #[case("/home/meow/x", "/home/me", "/home/meow/x")]
+    #[case("/home/x", "/home/me", "/home/x")]
+    #[case("home/me/x", "/home/me", "home/me/x")]
+    #[case(r"C:\Users\me\x", r"C:\Users\me", "~/x")]
+    #[case(r"D:\Users\me\x", r"C:\Users\me", r"D:\Users\me\x")]
+    fn contract_home_at(#[case] path: &str, #[case] home: &str, #[case] expected: &str) {
+        // arrange
+        let path = Path::from_str(path).unwrap();
+        let home = Path::from_str(home).unwrap();
+
+        // act
+        let contracted = path.contract_home_at(&home);
+
+        // assert
+        assert_eq!(contracted, Path::from_str(expected).unwrap());
+    }
+
     #[cfg(feature = "std")]
     #[rstest]
     #[case(r"~/a/b/./c/../d/e.txt", r"~/a/b/d/e.txt")]
@@ -1326,16 +2015,32 @@ mod test {
         assert_eq!(resolved, expected);
     }
 
-    #[cfg(feature = "std")]
     #[rstest]
-    fn resolve_at_cwd() {
-        let path = Path::from_str(".local/").unwrap();
+    #[case(".local/", "/home/brage/dev/code/canonic/.local/")]
+    #[case("../x", "/home/brage/dev/code/x")]
+    #[case("/etc/hosts", "/etc/hosts")]
+    fn resolve_at_cwd_in(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let env = MemoryEnv::new()
+            .with_current_dir(Path::from_str("/home/brage/dev/code/canonic/").unwrap());
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let resolved = path.resolve_at_cwd_in(&env).unwrap();
+
+        // assert
+        assert_eq!(resolved, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    fn with_cwd_base_in_without_cwd() {
+        // arrange
+        let path = Path::from_str("x").unwrap();
 
-        let resolved = path.resolve_at_cwd().unwrap();
+        // act
+        let result = path.with_cwd_base_in(&MemoryEnv::new());
 
-        assert_eq!(
-            resolved,
-            Path::from_str("/home/brage/dev/code/canonic/.local/").unwrap()
-        );
+        // assert
+        assert!(result.is_err());
     }
 }
@@ -0,0 +1,604 @@
+//! Glob patterns matched lexically against paths, segment by segment.
+
+use core::{fmt, str::FromStr};
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    parser,
+    path::{self, Drive, Path, Prefix, Root},
+};
+
+/// Whether letters in a [`PathPattern`] match letters of the other case.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    #[default]
+    Sensitive,
+    Insensitive,
+}
+
+/// Whether wildcards in a [`PathPattern`] match segments starting with a `.`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LeadingDot {
+    /// Segments starting with a `.` are only matched by pattern segments which
+    /// start with a literal `.`, as in a shell.
+    #[default]
+    Explicit,
+    /// Segments starting with a `.` are matched like any other.
+    Wildcard,
+}
+
+/// A glob pattern over path segments.
+///
+/// Patterns are parsed like paths, so `\` is a separator and a leading `/`,
+/// drive or UNC root anchors the pattern to it. Within a segment `*` matches any
+/// run of characters, `?` matches one character, `[a-z]` and `[!a-z]` match
+/// one character in or out of a set and `{a,b}` matches either alternative. A
+/// segment of `**` matches any number of segments. A trailing separator only
+/// matches directories.
+///
+/// Matching is purely lexical. Paths are compared as written, so they should
+/// usually be resolved first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPattern {
+    pattern: String,
+    prefix: Option<Prefix>,
+    drive: Option<Drive>,
+    root: Option<Root>,
+    pub(crate) segments: Vec<SegmentPattern>,
+    dir_only: bool,
+    case: CaseSensitivity,
+    leading_dot: LeadingDot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SegmentPattern {
+    Globstar,
+    /// The alternatives left after expanding braces.
+    Glob(Vec<Vec<Token>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Token {
+    Literal(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl PathPattern {
+    pub fn new(pattern: &str) -> Result<Self, &'static str> {
+        let path = parser::parse_path(pattern)?;
+
+        if path.drive.is_some() && path.root.is_none() {
+            return Err("patterns cannot be drive-relative, write `[x]:` to match `x:`");
+        }
+
+        let raw = raw_segments(&path);
+
+        let segments = raw
+            .iter()
+            .map(|segment| match segment.as_str() {
+                "**" => Ok(SegmentPattern::Globstar),
+                _ => parse_segment(segment).map(SegmentPattern::Glob),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(PathPattern {
+            pattern: pattern.to_string(),
+            prefix: path.prefix,
+            drive: path.drive,
+            root: path.root,
+            segments,
+            dir_only: pattern.ends_with(['/', '\\']),
+            case: CaseSensitivity::default(),
+            leading_dot: LeadingDot::default(),
+        })
+    }
+
+    /// Builds a relative pattern from segments which are already split, so
+    /// that a `\` in them is matched literally rather than as a separator.
+    pub(crate) fn from_segments(segments: &[String], dir_only: bool) -> Result<Self, &'static str> {
+        let mut pattern = segments.join("/");
+
+        if dir_only {
+            pattern.push('/');
+        }
+
+        Ok(PathPattern {
+            pattern,
+            prefix: None,
+            drive: None,
+            root: None,
+            segments: segments
+                .iter()
+                .map(|segment| match segment.as_str() {
+                    "**" => Ok(SegmentPattern::Globstar),
+                    _ => parse_segment(segment).map(SegmentPattern::Glob),
+                })
+                .collect::<Result<_, _>>()?,
+            dir_only,
+            case: CaseSensitivity::default(),
+            leading_dot: LeadingDot::default(),
+        })
+    }
+
+    pub fn with_case_sensitivity(mut self, case: CaseSensitivity) -> Self {
+        self.case = case;
+        self
+    }
+
+    pub fn with_leading_dot(mut self, leading_dot: LeadingDot) -> Self {
+        self.leading_dot = leading_dot;
+        self
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns whether the pattern is anchored to a root, drive or prefix.
+    pub fn is_anchored(&self) -> bool {
+        path::is_anchored(&self.prefix, &self.drive, &self.root)
+    }
+
+    /// Returns whether `path` matches the whole pattern. An anchored pattern
+    /// only matches paths with the same root, drive and prefix, and a
+    /// relative pattern only matches relative paths.
+    pub fn matches(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+
+        if self.prefix != path.prefix || self.root != path.root {
+            return false;
+        }
+
+        let same_drive = match (&self.drive, &path.drive) {
+            (Some(l), Some(r)) => l.letter.eq_ignore_ascii_case(&r.letter),
+            (None, None) => true,
+            _ => false,
+        };
+
+        if !same_drive || (self.dir_only && !path.is_dir()) {
+            return false;
+        }
+
+        let segments = raw_segments(path);
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        self.matches_segments(&self.segments, &segments)
+    }
+
+    fn matches_segments(&self, patterns: &[SegmentPattern], segments: &[&str]) -> bool {
+        // `matched[p * width + s]` is whether `patterns[p..]` match
+        // `segments[s..]`, filled in from the end so every `**` is only tried
+        // once per segment.
+        let width = segments.len() + 1;
+        let mut matched = vec![false; (patterns.len() + 1) * width];
+        matched[patterns.len() * width + segments.len()] = true;
+
+        for (p, pattern) in patterns.iter().enumerate().rev() {
+            for s in (0..=segments.len()).rev() {
+                let segment = segments.get(s);
+
+                matched[p * width + s] = match pattern {
+                    SegmentPattern::Globstar => {
+                        matched[(p + 1) * width + s]
+                            || segment.is_some_and(|segment| {
+                                !self.is_hidden(segment) && matched[p * width + s + 1]
+                            })
+                    }
+                    SegmentPattern::Glob(alternatives) => segment.is_some_and(|segment| {
+                        matched[(p + 1) * width + s + 1]
+                            && alternatives
+                                .iter()
+                                .any(|tokens| self.matches_segment(tokens, segment))
+                    }),
+                };
+            }
+        }
+
+        matched[0]
+    }
+
+    /// Returns whether `name` matches the segment pattern at `index`, which
+    /// mustn't be a `**`.
+    pub(crate) fn matches_name(&self, index: usize, name: &str) -> bool {
+        match &self.segments[index] {
+            SegmentPattern::Glob(alternatives) => alternatives
+                .iter()
+                .any(|tokens| self.matches_segment(tokens, name)),
+            SegmentPattern::Globstar => unreachable!("`**` matches segments, not names"),
+        }
+    }
+
+    /// The only name the segment pattern at `index` matches, if it has no
+    /// wildcards and matches case-sensitively.
+    pub(crate) fn literal(&self, index: usize) -> Option<String> {
+        let SegmentPattern::Glob(alternatives) = &self.segments[index] else {
+            return None;
+        };
+
+        match alternatives.as_slice() {
+            [tokens] if self.case == CaseSensitivity::Sensitive => tokens
+                .iter()
+                .map(|token| match token {
+                    Token::Literal(c) => Some(*c),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_hidden(&self, segment: &str) -> bool {
+        self.leading_dot == LeadingDot::Explicit && segment.starts_with('.')
+    }
+
+    fn matches_segment(&self, tokens: &[Token], segment: &str) -> bool {
+        if self.is_hidden(segment) && tokens.first() != Some(&Token::Literal('.')) {
+            return false;
+        }
+
+        let chars: Vec<char> = segment.chars().collect();
+        self.matches_tokens(tokens, &chars)
+    }
+
+    fn matches_tokens(&self, tokens: &[Token], chars: &[char]) -> bool {
+        let (mut t, mut c) = (0, 0);
+        // The token after the last `*` seen and the first char it hasn't
+        // matched yet, to retry from when a later token fails.
+        let mut star: Option<(usize, usize)> = None;
+
+        while c < chars.len() {
+            match tokens.get(t) {
+                Some(Token::Star) => {
+                    t += 1;
+                    star = Some((t, c));
+                }
+                Some(token) if self.matches_char(token, chars[c]) => {
+                    t += 1;
+                    c += 1;
+                }
+                _ => {
+                    let Some((star_t, star_c)) = star else {
+                        return false;
+                    };
+
+                    t = star_t;
+                    c = star_c + 1;
+                    star = Some((star_t, c));
+                }
+            }
+        }
+
+        tokens[t..].iter().all(|token| *token == Token::Star)
+    }
+
+    fn matches_char(&self, token: &Token, c: char) -> bool {
+        match token {
+            Token::Literal(l) => self.chars_eq(*l, c),
+            Token::Any => true,
+            Token::Class { negated, ranges } => {
+                let in_class = ranges
+                    .iter()
+                    .any(|(start, end)| self.variants(c).any(|c| (*start..=*end).contains(&c)));
+                in_class != *negated
+            }
+            Token::Star => unreachable!("`*` matches runs of chars"),
+        }
+    }
+
+    fn chars_eq(&self, l: char, r: char) -> bool {
+        match self.case {
+            CaseSensitivity::Sensitive => l == r,
+            CaseSensitivity::Insensitive => l == r || l.to_lowercase().eq(r.to_lowercase()),
+        }
+    }
+
+    /// `c`, then its other cases if matching is case-insensitive.
+    fn variants(&self, c: char) -> impl Iterator<Item = char> {
+        let insensitive = self.case == CaseSensitivity::Insensitive;
+        let lower = c.to_lowercase().filter(move |_| insensitive);
+        let upper = c.to_uppercase().filter(move |_| insensitive);
+        core::iter::once(c).chain(lower).chain(upper)
+    }
+}
+
+/// The segments of `path` as written. A `:` in the last segment parses as a
+/// stream, which is put back so that patterns can match it.
+pub(crate) fn raw_segments(path: &Path) -> Vec<String> {
+    let mut segments: Vec<String> = path.segments.iter().map(|s| s.0.clone()).collect();
+
+    if let (Some(stream), Some(last)) = (&path.stream, segments.last_mut()) {
+        last.push(':');
+        last.push_str(&stream.name);
+
+        if let Some(stream_type) = &stream.stream_type {
+            last.push(':');
+            last.push_str(stream_type);
+        }
+    }
+
+    segments
+}
+
+/// Parses a segment into its alternatives, with every brace expanded.
+fn parse_segment(segment: &str) -> Result<Vec<Vec<Token>>, &'static str> {
+    let chars: Vec<char> = segment.chars().collect();
+    let mut index = 0;
+    let alternatives = parse_sequence(&chars, &mut index, false)?;
+
+    Ok(alternatives)
+}
+
+/// Parses tokens up to the end of the segment or, inside braces, up to the next
+/// `,` or `}`. Returns every expansion of the sequence.
+fn parse_sequence(
+    chars: &[char],
+    index: &mut usize,
+    in_braces: bool,
+) -> Result<Vec<Vec<Token>>, &'static str> {
+    let mut expansions = vec![Vec::new()];
+
+    while let Some(&c) = chars.get(*index) {
+        if in_braces && (c == ',' || c == '}') {
+            break;
+        }
+
+        *index += 1;
+
+        let tokens = match c {
+            '*' => {
+                if chars.get(*index) == Some(&'*') {
+                    *index += 1;
+                }
+
+                vec![vec![Token::Star]]
+            }
+            '?' => vec![vec![Token::Any]],
+            '[' => vec![vec![parse_class(chars, index)?]],
+            '{' => parse_braces(chars, index)?,
+            c => vec![vec![Token::Literal(c)]],
+        };
+
+        expansions = expansions
+            .iter()
+            .flat_map(|prefix| {
+                tokens.iter().map(move |suffix| {
+                    let mut expansion = prefix.clone();
+                    expansion.extend(suffix.iter().cloned());
+                    expansion
+                })
+            })
+            .collect();
+    }
+
+    Ok(expansions)
+}
+
+fn parse_braces(chars: &[char], index: &mut usize) -> Result<Vec<Vec<Token>>, &'static str> {
+    let mut alternatives = Vec::new();
+
+    loop {
+        alternatives.extend(parse_sequence(chars, index, true)?);
+
+        match chars.get(*index) {
+            Some(',') => *index += 1,
+            Some('}') => {
+                *index += 1;
+                return Ok(alternatives);
+            }
+            _ => return Err("unclosed brace in pattern"),
+        }
+    }
+}
+
+fn parse_class(chars: &[char], index: &mut usize) -> Result<Token, &'static str> {
+    let negated = matches!(chars.get(*index), Some('!' | '^'));
+
+    if negated {
+        *index += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+
+    loop {
+        let Some(&c) = chars.get(*index) else {
+            return Err("unclosed character class in pattern");
+        };
+
+        *index += 1;
+
+        if c == ']' && !first {
+            return Ok(Token::Class { negated, ranges });
+        }
+
+        first = false;
+
+        match (chars.get(*index), chars.get(*index + 1)) {
+            (Some('-'), Some(&end)) if end != ']' => {
+                if end < c {
+                    return Err("character class ranges must be in order");
+                }
+
+                *index += 2;
+                ranges.push((c, end));
+            }
+            _ => ranges.push((c, c)),
+        }
+    }
+}
+
+impl FromStr for PathPattern {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PathPattern::new(s)
+    }
+}
+
+impl fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("src/**/*.{rs,toml}", "src/lib.rs", true)]
+    #[case("src/**/*.{rs,toml}", "src/a/b/Cargo.toml", true)]
+    #[case("src/**/*.{rs,toml}", "src/a/b/notes.md", false)]
+    #[case("src/**/*.{rs,toml}", "tests/lib.rs", false)]
+    #[case("src/**", "src", true)]
+    #[case("src/**", "src/a/b", true)]
+    #[case("**", "a/b", true)]
+    #[case("*.rs", "a/lib.rs", false)]
+    #[case("?.rs", "a.rs", true)]
+    #[case("?.rs", "ab.rs", false)]
+    #[case("[a-c]*", "banana", true)]
+    #[case("[!a-c]*", "banana", false)]
+    #[case("[]]", "]", true)]
+    #[case("ab[:]c", "ab:c", true)]
+    #[case("a{b,{c,d}e}f", "adef", true)]
+    #[case("a{b,{c,d}e}f", "abef", false)]
+    #[case("a{,b}", "a", true)]
+    #[case("a**b", "axyzb", true)]
+    #[case(r"src\*.rs", "src/lib.rs", true)]
+    #[case("src/", "src/", true)]
+    #[case("src/", "src", false)]
+    #[case("src", "src/", true)]
+    #[case("dir/[a]:*.txt", "dir/a:b.txt", true)]
+    #[case("*a*b*c", "xaybzc", true)]
+    #[case("*a*b*c", "xaybzcd", false)]
+    #[case("a*", "a", true)]
+    #[case("**/a/**/b", "x/a/y/a/b", true)]
+    #[case("**/a/**/b", "x/a/y/b/c", false)]
+    fn matches(#[case] pattern: &str, #[case] path: &str, #[case] expected: bool) {
+        // arrange
+        let pattern = PathPattern::new(pattern).unwrap();
+
+        // act
+        let matched = pattern.matches(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(matched, expected);
+    }
+
+    #[rstest]
+    #[case("/src/*.rs", "/src/lib.rs", true)]
+    #[case("/src/*.rs", "src/lib.rs", false)]
+    #[case("src/*.rs", "/src/lib.rs", false)]
+    #[case(r"C:\Users\*", r"c:\Users\me", true)]
+    #[case(r"C:\Users\*", r"D:\Users\me", false)]
+    #[case(r"C:\Users\*", r"\Users\me", false)]
+    #[case(r"\\server\share\**", r"\\server\share\a\b", true)]
+    #[case(r"\\server\share\**", r"/server/share/a", false)]
+    fn matches_anchored(#[case] pattern: &str, #[case] path: &str, #[case] expected: bool) {
+        // arrange
+        let pattern = PathPattern::new(pattern).unwrap();
+
+        // act
+        let matched = pattern.matches(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(matched, expected);
+    }
+
+    #[rstest]
+    #[case(LeadingDot::Explicit, "*", ".git", false)]
+    #[case(LeadingDot::Explicit, ".*", ".git", true)]
+    #[case(LeadingDot::Explicit, "**/*.rs", ".cargo/a.rs", false)]
+    #[case(LeadingDot::Explicit, "[.]git", ".git", false)]
+    #[case(LeadingDot::Wildcard, "*", ".git", true)]
+    #[case(LeadingDot::Wildcard, "**/*.rs", ".cargo/a.rs", true)]
+    fn matches_leading_dot(
+        #[case] leading_dot: LeadingDot,
+        #[case] pattern: &str,
+        #[case] path: &str,
+        #[case] expected: bool,
+    ) {
+        // arrange
+        let pattern = PathPattern::new(pattern)
+            .unwrap()
+            .with_leading_dot(leading_dot);
+
+        // act
+        let matched = pattern.matches(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(matched, expected);
+    }
+
+    #[rstest]
+    #[case(CaseSensitivity::Sensitive, "*.RS", "lib.rs", false)]
+    #[case(CaseSensitivity::Insensitive, "*.RS", "lib.rs", true)]
+    #[case(CaseSensitivity::Insensitive, "[A-C]*", "banana", true)]
+    #[case(CaseSensitivity::Insensitive, "Straße", "STRASSE", false)]
+    fn matches_case_sensitivity(
+        #[case] case: CaseSensitivity,
+        #[case] pattern: &str,
+        #[case] path: &str,
+        #[case] expected: bool,
+    ) {
+        // arrange
+        let pattern = PathPattern::new(pattern)
+            .unwrap()
+            .with_case_sensitivity(case);
+
+        // act
+        let matched = pattern.matches(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(matched, expected);
+    }
+
+    #[rstest]
+    #[case("a[b", "unclosed character class in pattern")]
+    #[case("a{b,c", "unclosed brace in pattern")]
+    #[case("[z-a]", "character class ranges must be in order")]
+    #[case("a//b", "path segments cannot be empty")]
+    #[case(
+        "a:*.txt",
+        "patterns cannot be drive-relative, write `[x]:` to match `x:`"
+    )]
+    #[case(
+        "C:src/*",
+        "patterns cannot be drive-relative, write `[x]:` to match `x:`"
+    )]
+    fn new_should_fail(#[case] pattern: &str, #[case] expected: &str) {
+        // act
+        let pattern = PathPattern::new(pattern);
+
+        // assert
+        assert_eq!(pattern, Err(expected));
+    }
+
+    #[rstest]
+    fn matches_pathological_patterns_quickly() {
+        // arrange
+        let name = "a".repeat(64);
+        let path = Path::from_str(&["a"; 64].join("/")).unwrap();
+        let stars = PathPattern::new(&alloc::format!("{}b", "a*".repeat(32))).unwrap();
+        let globstars = PathPattern::new(&["**/a"; 16].join("/")).unwrap();
+
+        // act
+        let star_match = stars.matches(Path::from_str(&name).unwrap());
+        let globstar_match = globstars.matches(&path);
+
+        // assert
+        assert!(!star_match);
+        assert!(globstar_match);
+    }
+}
@@ -0,0 +1,352 @@
+use core::fmt;
+
+use alloc::{
+    collections::VecDeque,
+    string::{String, ToString},
+};
+
+use crate::{
+    env::PathEnvironment,
+    filesystem::{FileSystem, FsError},
+    packed_list::PathSegmentList,
+    path::{Path, Resolution},
+};
+
+/// The most symlinks [`Path::resolve_physical`] follows, as in Linux.
+pub const DEFAULT_SYMLINK_HOPS: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhysicalError {
+    /// More than `limit` symlinks were followed. `segment` is the symlink
+    /// which would have been one too many.
+    Loop { segment: String, limit: usize },
+    /// Reading the filesystem failed for a reason other than a missing file.
+    Fs(FsError),
+    /// A symlink target or the joined path wasn't a valid path.
+    Path(&'static str),
+}
+
+impl PhysicalError {
+    /// A static description of the error, for the APIs which report errors as
+    /// plain strings.
+    pub fn message(&self) -> &'static str {
+        match self {
+            PhysicalError::Loop { .. } => "too many levels of symbolic links",
+            PhysicalError::Fs(_) => "couldn't read the filesystem",
+            PhysicalError::Path(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for PhysicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhysicalError::Loop { segment, limit } => {
+                write!(f, "{segment}: more than {limit} symlinks were followed")
+            }
+            PhysicalError::Fs(error) => write!(f, "{error}"),
+            PhysicalError::Path(message) => f.write_str(message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PhysicalError {}
+
+impl Path {
+    /// Resolves this path against the filesystem, following symlinks, without
+    /// requiring all of it to exist.
+    ///
+    /// Relative paths are first joined onto the current directory. Segments
+    /// are then looked up one at a time. Each symlink is replaced by its target
+    /// before any `..` after it is applied, the way the kernel does. Once a
+    /// segment is missing, the rest of the path is appended lexically, so the
+    /// path of a file which is about to be created can be resolved too.
+    ///
+    /// At most [`DEFAULT_SYMLINK_HOPS`] symlinks are followed.
+    #[cfg(feature = "std")]
+    pub fn resolve_physical(&self) -> Result<Self, PhysicalError> {
+        self.resolve_physical_with(DEFAULT_SYMLINK_HOPS)
+    }
+
+    /// Resolves this path like [`Path::resolve_physical`], following at most
+    /// `max_hops` symlinks.
+    #[cfg(feature = "std")]
+    pub fn resolve_physical_with(&self, max_hops: usize) -> Result<Self, PhysicalError> {
+        let path = if self.is_relative() {
+            self.with_cwd_base().map_err(PhysicalError::Path)?
+        } else {
+            self.clone()
+        };
+
+        path.resolve_physical_with_in(&crate::filesystem::StdFs, max_hops)
+    }
+
+    /// Resolves the path like [`Path::resolve_with`], looking up symlinks for
+    /// [`Resolution::Physical`] in `fs`. Unlike [`Path::resolve_with`], the
+    /// error says why physical resolution failed.
+    pub fn resolve_with_in(
+        self,
+        env: &impl PathEnvironment,
+        resolution: Resolution,
+        fs: &(impl FileSystem + ?Sized),
+    ) -> Result<Self, PhysicalError> {
+        match resolution {
+            Resolution::Physical => self
+                .expand_tilde(env, false)
+                .map_err(PhysicalError::Path)?
+                .resolve_physical_at(env, fs),
+            _ => self
+                .resolve_with(env, resolution)
+                .map_err(PhysicalError::Path),
+        }
+    }
+
+    /// Makes the path absolute against the current directory of `env` if it's
+    /// relative, then resolves it physically against `fs`.
+    pub(crate) fn resolve_physical_at(
+        self,
+        env: &impl PathEnvironment,
+        fs: &(impl FileSystem + ?Sized),
+    ) -> Result<Self, PhysicalError> {
+        let path = if self.is_relative() {
+            self.with_cwd_base_in(env).map_err(PhysicalError::Path)?
+        } else {
+            self
+        };
+
+        path.resolve_physical_in(fs)
+    }
+
+    /// Resolves this absolute path like [`Path::resolve_physical`] against
+    /// `fs`.
+    pub fn resolve_physical_in(
+        &self,
+        fs: &(impl FileSystem + ?Sized),
+    ) -> Result<Self, PhysicalError> {
+        self.resolve_physical_with_in(fs, DEFAULT_SYMLINK_HOPS)
+    }
+
+    /// Resolves this absolute path like [`Path::resolve_physical_with`] against
+    /// `fs`.
+    pub fn resolve_physical_with_in(
+        &self,
+        fs: &(impl FileSystem + ?Sized),
+        max_hops: usize,
+    ) -> Result<Self, PhysicalError> {
+        if self.is_relative() {
+            return Err(PhysicalError::Path(
+                "relative paths must be made absolute before they're resolved physically",
+            ));
+        }
+
+        let mut queue: VecDeque<String> = self.segments.iter().map(|s| s.0.clone()).collect();
+        let mut resolved = Path {
+            segments: PathSegmentList::new(),
+            stream: None,
+            is_dir: true,
+            ..self.clone()
+        };
+        // The number of segments which existed before the first missing one.
+        let mut missing_from: Option<usize> = None;
+        let mut hops = 0;
+
+        while let Some(segment) = queue.pop_front() {
+            match segment.as_str() {
+                "." => continue,
+                ".." => {
+                    resolved.segments.remove_last();
+
+                    if missing_from.is_some_and(|len| resolved.segments.len() <= len) {
+                        missing_from = None;
+                    }
+
+                    continue;
+                }
+                _ => {}
+            }
+
+            resolved.segments.push(segment.clone());
+
+            if missing_from.is_some() {
+                continue;
+            }
+
+            let metadata = match fs.symlink_metadata(&resolved) {
+                Ok(metadata) => metadata,
+                Err(FsError::NotFound(_)) => {
+                    missing_from = Some(resolved.segments.len() - 1);
+                    continue;
+                }
+                Err(e) => return Err(PhysicalError::Fs(e)),
+            };
+
+            if !metadata.is_symlink() {
+                continue;
+            }
+
+            hops += 1;
+
+            if hops > max_hops {
+                return Err(PhysicalError::Loop {
+                    segment,
+                    limit: max_hops,
+                });
+            }
+
+            let target = fs.read_link(&resolved).map_err(PhysicalError::Fs)?;
+
+            resolved.segments.remove_last();
+
+            if target.is_absolute() {
+                resolved = Path {
+                    segments: PathSegmentList::new(),
+                    stream: None,
+                    is_dir: true,
+                    ..target.clone()
+                };
+            }
+
+            let rest = core::mem::take(&mut queue);
+            queue.extend(target.segments.iter().map(|s| s.0.to_string()));
+            queue.extend(rest);
+        }
+
+        resolved.stream = self.stream.clone();
+        resolved.is_dir = self.is_dir || resolved.segments.len() == 0;
+        Ok(resolved)
+    }
+}
+
+#[cfg(all(test, feature = "std", unix))]
+mod test {
+    use core::str::FromStr;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::{env::MemoryEnv, filesystem::StdFs, test_support::TempTree};
+
+    /// Creates a fresh tree in the temp directory:
+    ///
+    /// ```text
+    /// root/a/b/
+    /// root/link -> a/b
+    /// root/absolute -> <root>/a
+    /// root/l1 -> l2
+    /// root/l2 -> l1
+    /// root/self -> .
+    /// ```
+    fn tree() -> TempTree {
+        let tree = TempTree::new("physical").with_dir("a/b");
+        let absolute = tree.join("a");
+
+        tree.with_symlink("a/b", "link")
+            .with_symlink(absolute, "absolute")
+            .with_symlink("l2", "l1")
+            .with_symlink("l1", "l2")
+            .with_symlink(".", "self")
+    }
+
+    #[rstest]
+    #[case("a/b", "a/b")]
+    #[case("link", "a/b")]
+    #[case("link/../c", "a/c")]
+    #[case("link/new/file.txt", "a/b/new/file.txt")]
+    #[case("absolute/b/", "a/b/")]
+    #[case("self/self/link/..", "a/")]
+    #[case("missing/dir/../file.txt", "missing/file.txt")]
+    #[case("missing/../link", "a/b")]
+    #[case("missing/dir/../../link/..", "a/")]
+    #[case("a/b/../../..", "..")]
+    fn resolve_physical(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let root = tree();
+        let root_path = root.path();
+        let path = root_path.join(Path::from_str(path).unwrap()).unwrap();
+
+        // act
+        let resolved = path.resolve_physical().unwrap();
+
+        // assert
+        let expected = root_path
+            .join(Path::from_str(expected).unwrap())
+            .unwrap()
+            .resolve()
+            .unwrap();
+        assert_eq!(resolved, expected);
+    }
+
+    #[rstest]
+    #[case(Resolution::Lexical, "link/..", "a/..")]
+    #[case(Resolution::Logical, "link/..", "a/..")]
+    #[case(Resolution::Physical, "link/..", "a")]
+    #[case(Resolution::Physical, "link/../b/..", "a")]
+    fn resolve_with_resolution(
+        #[case] resolution: Resolution,
+        #[case] path: &str,
+        #[case] expected: &str,
+    ) {
+        // arrange
+        let root = tree();
+        let root_path = root.path();
+        let env = MemoryEnv::new().with_current_dir(&root_path);
+        let path = root_path.join(Path::from_str(path).unwrap()).unwrap();
+
+        // act
+        let resolved = path.resolve_with(&env, resolution).unwrap();
+
+        // assert
+        let expected = root_path
+            .join(Path::from_str(expected).unwrap())
+            .unwrap()
+            .resolve()
+            .unwrap();
+        assert_eq!(resolved.segments, expected.segments);
+    }
+
+    #[rstest]
+    fn resolve_with_in_keeps_the_error() {
+        // arrange
+        let root = tree();
+        let env = MemoryEnv::new().with_current_dir(root.path());
+        let path = Path::from_str("l1").unwrap();
+
+        // act
+        let resolved = path
+            .clone()
+            .resolve_with_in(&env, Resolution::Physical, &StdFs);
+        let message = path.resolve_with(&env, Resolution::Physical);
+
+        // assert
+        assert_eq!(
+            resolved,
+            Err(PhysicalError::Loop {
+                segment: "l1".to_string(),
+                limit: DEFAULT_SYMLINK_HOPS
+            })
+        );
+        assert_eq!(message, Err("too many levels of symbolic links"));
+    }
+
+    #[rstest]
+    #[case("l1/x", 40, "l1")]
+    #[case("l2", 3, "l1")]
+    #[case("link/x", 0, "link")]
+    fn resolve_physical_loop(#[case] path: &str, #[case] limit: usize, #[case] segment: &str) {
+        // arrange
+        let root = tree();
+        let path = root.path().join(Path::from_str(path).unwrap()).unwrap();
+
+        // act
+        let resolved = path.resolve_physical_with(limit);
+
+        // assert
+        assert_eq!(
+            resolved,
+            Err(PhysicalError::Loop {
+                segment: segment.to_string(),
+                limit
+            })
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Rewriting path prefixes, in the manner of `--remap-path-prefix`.
+
+use core::fmt;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{path::Path, pattern::raw_segments};
+
+/// A table of prefix rules, each rewriting paths under `from` to be under `to`.
+///
+/// Prefixes match by whole segments, so `/src` is a prefix of `/src/lib.rs`
+/// but not of `/srcs`. Nested prefixes must map to targets nested the same
+/// way, so every path remaps the same whichever rule is used, and remapping
+/// can always be undone by [`PrefixMap::unmap`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PrefixMap {
+    rules: Vec<(Path, Path)>,
+}
+
+/// The reason a rule couldn't be added to a [`PrefixMap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixMapError {
+    /// The prefix is already mapped somewhere else.
+    DuplicatePrefix(String),
+    /// Another prefix is already mapped to the target, so it couldn't be
+    /// mapped back.
+    DuplicateTarget(String),
+    /// The rules' prefixes or targets are nested, but not both in the same
+    /// way, so a path under both could be mapped two ways.
+    Overlap(String, String),
+}
+
+impl fmt::Display for PrefixMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixMapError::DuplicatePrefix(prefix) => {
+                write!(f, "{prefix}: prefix is already mapped elsewhere")
+            }
+            PrefixMapError::DuplicateTarget(target) => {
+                write!(f, "{target}: another prefix is already mapped here")
+            }
+            PrefixMapError::Overlap(first, second) => {
+                write!(f, "prefix rules `{first}` and `{second}` overlap")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PrefixMapError {}
+
+impl PrefixMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule mapping paths under `from` to the same paths under `to`.
+    /// Adding a rule which is already in the map does nothing.
+    pub fn with_rule(
+        mut self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+    ) -> Result<Self, PrefixMapError> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+
+        for (existing_from, existing_to) in &self.rules {
+            let from_nesting = nesting(existing_from, from);
+            let to_nesting = nesting(existing_to, to);
+
+            if from_nesting == to_nesting {
+                if from_nesting
+                    .as_ref()
+                    .is_some_and(|(_, suffix)| suffix.is_empty())
+                {
+                    return Ok(self);
+                }
+
+                continue;
+            }
+
+            let is_same = |nesting: &Option<(bool, Vec<String>)>| {
+                nesting
+                    .as_ref()
+                    .is_some_and(|(_, suffix)| suffix.is_empty())
+            };
+
+            return Err(if is_same(&from_nesting) {
+                PrefixMapError::DuplicatePrefix(from.rendered())
+            } else if is_same(&to_nesting) {
+                PrefixMapError::DuplicateTarget(to.rendered())
+            } else {
+                PrefixMapError::Overlap(
+                    rule_string(existing_from, existing_to),
+                    rule_string(from, to),
+                )
+            });
+        }
+
+        self.rules.push((from.clone(), to.clone()));
+        Ok(self)
+    }
+
+    /// Returns the rules, in the order they were added.
+    pub fn rules(&self) -> impl Iterator<Item = (&Path, &Path)> {
+        self.rules.iter().map(|(from, to)| (from, to))
+    }
+
+    /// Rewrites `path` by the rule with the longest prefix of it, or returns
+    /// [`None`] if no prefix matches.
+    pub fn remap(&self, path: impl AsRef<Path>) -> Option<Path> {
+        apply(
+            self.rules.iter().map(|(from, to)| (from, to)),
+            path.as_ref(),
+        )
+    }
+
+    /// Undoes [`PrefixMap::remap`], rewriting `path` by the rule with the
+    /// longest target prefix of it.
+    pub fn unmap(&self, path: impl AsRef<Path>) -> Option<Path> {
+        apply(
+            self.rules.iter().map(|(from, to)| (to, from)),
+            path.as_ref(),
+        )
+    }
+
+    /// Returns the map with every rule reversed.
+    pub fn inverse(&self) -> PrefixMap {
+        PrefixMap {
+            rules: self
+                .rules
+                .iter()
+                .map(|(from, to)| (to.clone(), from.clone()))
+                .collect(),
+        }
+    }
+}
+
+fn apply<'a>(rules: impl Iterator<Item = (&'a Path, &'a Path)>, path: &Path) -> Option<Path> {
+    let (from, to) = rules
+        .filter(|(from, _)| path.starts_with(from))
+        .max_by_key(|(from, _)| from.segments.len())?;
+
+    path.rebase(from, to)
+}
+
+/// Returns the segments of `path` after `prefix`, if it's a prefix of `path`.
+fn strip(path: &Path, prefix: &Path) -> Option<Vec<String>> {
+    path.strip_prefix(prefix).map(|rest| raw_segments(&rest))
+}
+
+/// Returns which of the paths is a prefix of the other, true for `r`, and the
+/// segments after it.
+fn nesting(l: &Path, r: &Path) -> Option<(bool, Vec<String>)> {
+    strip(r, l)
+        .map(|suffix| (false, suffix))
+        .or_else(|| strip(l, r).map(|suffix| (true, suffix)))
+}
+
+fn rule_string(from: &Path, to: &Path) -> String {
+    let mut rule = from.rendered();
+    rule.push_str(" => ");
+    rule.push_str(&to.rendered());
+    rule
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use alloc::vec;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn map(rules: Vec<(&str, &str)>) -> Result<PrefixMap, PrefixMapError> {
+        rules
+            .into_iter()
+            .try_fold(PrefixMap::new(), |map, (from, to)| {
+                map.with_rule(Path::from_str(from).unwrap(), Path::from_str(to).unwrap())
+            })
+    }
+
+    #[rstest]
+    #[case(vec![("/home/ci/build/123/src", "/src")], "/home/ci/build/123/src/lib.rs", Some("/src/lib.rs"))]
+    #[case(vec![("/home/ci/build/123/src", "/src")], "/home/ci/build/123/src", Some("/src"))]
+    #[case(vec![("/home/ci/build/123/src", "/src")], "/home/ci/build/123/srcs/lib.rs", None)]
+    #[case(vec![("/home/ci/build/123/src", "/src")], "home/ci/build/123/src/lib.rs", None)]
+    #[case(vec![("/a", "/x"), ("/a/b", "/x/b")], "/a/b/c", Some("/x/b/c"))]
+    #[case(vec![("/a", "/x"), ("/b", "/y")], "/b/c/", Some("/y/c/"))]
+    #[case(vec![("C:\\work", "/mnt/work")], "C:\\work\\a.txt", Some("/mnt/work/a.txt"))]
+    #[case(vec![("C:\\work", "/mnt/work")], "D:\\work\\a.txt", None)]
+    #[case(vec![("C:/data", "D:/data")], "C:/data/file.txt:stream", Some("D:/data/file.txt:stream"))]
+    fn remap(#[case] rules: Vec<(&str, &str)>, #[case] path: &str, #[case] expected: Option<&str>) {
+        // arrange
+        let map = map(rules).unwrap();
+
+        // act
+        let remapped = map.remap(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(remapped, expected.map(|e| Path::from_str(e).unwrap()));
+    }
+
+    #[rstest]
+    #[case(vec![("/home/ci/build", "/src")], "/src/lib.rs", Some("/home/ci/build/lib.rs"))]
+    #[case(vec![("/home/ci/build", "/src")], "/home/ci/build/lib.rs", None)]
+    #[case(vec![("/a", "/x"), ("/a/b", "/x/b")], "/x/b/c", Some("/a/b/c"))]
+    fn unmap(#[case] rules: Vec<(&str, &str)>, #[case] path: &str, #[case] expected: Option<&str>) {
+        // arrange
+        let map = map(rules).unwrap();
+
+        // act
+        let unmapped = map.unmap(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(unmapped, expected.map(|e| Path::from_str(e).unwrap()));
+        assert_eq!(
+            map.inverse().remap(Path::from_str(path).unwrap()),
+            expected.map(|e| Path::from_str(e).unwrap())
+        );
+    }
+
+    #[rstest]
+    #[case(vec![("/a", "/x"), ("/a", "/y")], PrefixMapError::DuplicatePrefix("/a".into()))]
+    #[case(vec![("/a", "/x"), ("/b", "/x")], PrefixMapError::DuplicateTarget("/x".into()))]
+    #[case(vec![("/a", "/x"), ("/a/b", "/y")], PrefixMapError::Overlap("/a => /x".into(), "/a/b => /y".into()))]
+    #[case(vec![("/a", "/x"), ("/b", "/x/y")], PrefixMapError::Overlap("/a => /x".into(), "/b => /x/y".into()))]
+    #[case(vec![("/a/b", "/x/b"), ("/a", "/y")], PrefixMapError::Overlap("/a/b => /x/b".into(), "/a => /y".into()))]
+    fn invalid(#[case] rules: Vec<(&str, &str)>, #[case] expected: PrefixMapError) {
+        // act
+        let result = map(rules);
+
+        // assert
+        assert_eq!(result, Err(expected));
+    }
+
+    #[rstest]
+    fn duplicate_rule() {
+        // act
+        let map = map(vec![("/a", "/x"), ("/a", "/x")]).unwrap();
+
+        // assert
+        assert_eq!(map.rules().count(), 1);
+    }
+}
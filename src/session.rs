@@ -0,0 +1,407 @@
+use alloc::vec::Vec;
+
+use crate::{
+    builder::DefaultEnv,
+    env::PathEnvironment,
+    path::{Drive, Path, Root},
+    windows::WindowsCwdState,
+};
+
+/// How a [`PathSession`] decides whether a directory can be entered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DirCheck {
+    /// Any path which could name a directory is accepted, without looking at
+    /// the filesystem.
+    #[default]
+    Lexical,
+    /// The directory must exist on the host filesystem.
+    #[cfg(feature = "std")]
+    FileSystem,
+}
+
+/// A virtual working directory, with one current directory per drive and a
+/// directory stack, as kept by a shell.
+///
+/// Relative paths are resolved against the session instead of the process, so
+/// any number of sessions can be used at once without racing on the process
+/// cwd.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathSession<E = DefaultEnv> {
+    cwd: Path,
+    drives: WindowsCwdState,
+    stack: Vec<Path>,
+    check: DirCheck,
+    env: E,
+}
+
+impl PathSession {
+    /// Creates a session whose current directory is `cwd`, which must be
+    /// absolute.
+    pub fn new(cwd: impl AsRef<Path>) -> Result<Self, &'static str> {
+        PathSession::with_environment(cwd, DefaultEnv::default())
+    }
+}
+
+impl<E: PathEnvironment> PathSession<E> {
+    /// Creates a session starting in the current directory of `env`, which is
+    /// also used to expand `~`.
+    pub fn from_env(env: E) -> Result<Self, &'static str> {
+        let cwd = env.current_dir()?;
+        PathSession::with_environment(cwd, env)
+    }
+
+    /// Creates a session whose current directory is `cwd`, expanding `~` with
+    /// the home directories of `env`.
+    pub fn with_environment(cwd: impl AsRef<Path>, env: E) -> Result<Self, &'static str> {
+        let cwd = cwd.as_ref();
+
+        if cwd.is_relative() || cwd.drive.is_some() && cwd.root.is_none() {
+            return Err("the working directory must be absolute");
+        }
+
+        let c_root = Path {
+            drive: Some(Drive { letter: 'C' }),
+            root: Some(Root::Normal),
+            ..Path::new()
+        };
+
+        let mut session = PathSession {
+            cwd: Path::new(),
+            drives: WindowsCwdState::new(c_root)?,
+            stack: Vec::new(),
+            check: DirCheck::default(),
+            env,
+        };
+
+        let cwd = session.resolve(cwd)?;
+        session.set_cwd(cwd)?;
+        Ok(session)
+    }
+
+    pub fn with_check(mut self, check: DirCheck) -> Self {
+        self.check = check;
+        self
+    }
+
+    pub fn current_dir(&self) -> &Path {
+        &self.cwd
+    }
+
+    /// Returns the current directory of the given drive. Drives which were never
+    /// visited default to their root, as they do on Windows.
+    pub fn drive_cwd(&self, letter: char) -> Path {
+        self.drives.drive_cwd(letter)
+    }
+
+    /// The directory stack, with the directory [`PathSession::popd`] returns to
+    /// last.
+    pub fn stack(&self) -> &[Path] {
+        &self.stack
+    }
+
+    /// Changes the current directory to `path`, which is resolved against the
+    /// session first.
+    pub fn cd(&mut self, path: impl AsRef<Path>) -> Result<&Path, &'static str> {
+        let cwd = self.enterable(path.as_ref())?;
+        self.set_cwd(cwd)?;
+        Ok(&self.cwd)
+    }
+
+    /// Changes the current directory to `path`, saving the current one on the
+    /// directory stack.
+    pub fn pushd(&mut self, path: impl AsRef<Path>) -> Result<&Path, &'static str> {
+        let cwd = self.enterable(path.as_ref())?;
+        let previous = self.cwd.clone();
+        self.set_cwd(cwd)?;
+        self.stack.push(previous);
+        Ok(&self.cwd)
+    }
+
+    /// Returns to the directory on top of the directory stack. The stack is left
+    /// untouched if that directory can't be entered.
+    pub fn popd(&mut self) -> Result<&Path, &'static str> {
+        let cwd = self.stack.last().ok_or("the directory stack is empty")?;
+        let cwd = self.enterable(cwd)?;
+        self.set_cwd(cwd)?;
+        self.stack.pop();
+        Ok(&self.cwd)
+    }
+
+    /// Makes `path` absolute against the session without resolving it.
+    ///
+    /// - `C:foo` is joined onto the current directory of drive `C`.
+    /// - `\foo` is rooted on the drive or share of the current directory.
+    /// - `foo` is joined onto the current directory.
+    /// - Absolute paths are returned unchanged.
+    pub fn absolutize(&self, path: impl AsRef<Path>) -> Result<Path, &'static str> {
+        let path = path.as_ref();
+
+        if path.prefix.is_some() || path.root == Some(Root::Unc) {
+            return Ok(path.clone());
+        }
+
+        let on_drive = self.cwd.drive.is_some() && self.cwd.prefix.is_none();
+
+        match (&path.drive, &path.root) {
+            (Some(_), Some(_)) => Ok(path.clone()),
+            (Some(_), None) => self.drives.absolutize(path),
+            (None, Some(_)) if on_drive => self.drives.absolutize(path),
+            (None, Some(_)) => {
+                let mut relative = path.clone();
+                relative.root = None;
+
+                let base = if self.cwd.root == Some(Root::Unc) {
+                    Path {
+                        prefix: self.cwd.prefix.clone(),
+                        root: Some(Root::Unc),
+                        segments: self.cwd.segments.iter().take(2).cloned().collect(),
+                        is_dir: true,
+                        ..Path::new()
+                    }
+                } else {
+                    Path {
+                        prefix: self.cwd.prefix.clone(),
+                        drive: self.cwd.drive.clone(),
+                        root: Some(Root::Normal),
+                        is_dir: true,
+                        ..Path::new()
+                    }
+                };
+
+                base.join(relative)
+            }
+            (None, None) => self.cwd.join(path),
+        }
+    }
+
+    /// Makes `path` absolute against the session and resolves it lexically,
+    /// expanding a leading `~` first. `..` never climbs above the root, which
+    /// includes the server and share of a UNC path.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Result<Path, &'static str> {
+        let path = path.as_ref().clone().expand_tilde(&self.env, false)?;
+        let mut path = self.absolutize(path)?;
+
+        let fixed = if path.root == Some(Root::Unc) { 2 } else { 0 };
+        let mut rest = Path {
+            segments: path.segments.iter().skip(fixed).cloned().collect(),
+            ..Path::new()
+        }
+        .resolve_lexically();
+
+        while let Some(head) = rest.segments.head
+            && rest.segments[head].value.0 == ".."
+        {
+            rest.segments.remove(head);
+        }
+
+        path.segments = path
+            .segments
+            .iter()
+            .take(fixed)
+            .cloned()
+            .chain(rest.segments)
+            .collect();
+
+        Ok(path)
+    }
+
+    fn enterable(&self, path: &Path) -> Result<Path, &'static str> {
+        let mut path = self.resolve(path)?;
+
+        if path.stream.is_some() {
+            return Err("a directory cannot have a stream");
+        }
+
+        path.is_dir = true;
+
+        match self.check {
+            DirCheck::Lexical => {}
+            #[cfg(feature = "std")]
+            DirCheck::FileSystem => {
+                let std_path = std::path::PathBuf::from(&path);
+
+                if !std_path.is_dir() {
+                    return Err("no such directory");
+                }
+            }
+        }
+
+        Ok(path)
+    }
+
+    fn set_cwd(&mut self, mut cwd: Path) -> Result<(), &'static str> {
+        cwd.is_dir = true;
+
+        if let Some(Drive { letter }) = cwd.drive
+            && cwd.prefix.is_none()
+        {
+            self.drives.set_drive_cwd(&cwd)?;
+            self.drives.set_current_drive(letter)?;
+        }
+
+        self.cwd = cwd;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::env::MemoryEnv;
+
+    fn session(cwd: &str) -> PathSession<MemoryEnv> {
+        let env = MemoryEnv::new().with_home_dir(Path::from_str("/home/me").unwrap());
+        PathSession::with_environment(Path::from_str(cwd).unwrap(), env).unwrap()
+    }
+
+    #[rstest]
+    #[case("/work", "src", "/work/src/")]
+    #[case("/work", "../etc/", "/etc/")]
+    #[case("/work", "../../..", "/")]
+    #[case("/work", "/tmp/./x", "/tmp/x/")]
+    #[case("/work", "~/notes", "/home/me/notes/")]
+    #[case(r"C:\work", r"..\src", r"C:\src\")]
+    #[case(r"C:\work", r"\src", r"C:\src\")]
+    #[case(r"C:\work", r"D:src", r"D:\src\")]
+    #[case(r"\\server\share\work", r"\src", r"\\server\share\src\")]
+    #[case(r"\\server\share\work", r"..\..\..", r"\\server\share\")]
+    #[case(r"\\server\share\work", r"..\..", r"\\server\share\")]
+    #[case(r"\\server\share", r"..\..\..\x", r"\\server\share\x\")]
+    fn cd(#[case] cwd: &str, #[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let mut session = session(cwd);
+
+        // act
+        let result = session.cd(Path::from_str(path).unwrap()).unwrap().clone();
+
+        // assert
+        assert_eq!(result, Path::from_str(expected).unwrap());
+        assert_eq!(session.current_dir(), &result);
+    }
+
+    #[rstest]
+    fn cd_repeatedly_above_a_share() {
+        // arrange
+        let mut session = session(r"\\server\share\work");
+        session.cd(Path::from_str(r"..\..\..").unwrap()).unwrap();
+
+        // act
+        let result = session
+            .cd(Path::from_str(r"..\..\..\x").unwrap())
+            .unwrap()
+            .clone();
+
+        // assert
+        assert_eq!(result, Path::from_str(r"\\server\share\x\").unwrap());
+    }
+
+    #[rstest]
+    #[case("relative")]
+    #[case("C:relative")]
+    fn new_should_fail(#[case] cwd: &str) {
+        // act
+        let session = PathSession::new(Path::from_str(cwd).unwrap());
+
+        // assert
+        assert!(session.is_err());
+    }
+
+    #[rstest]
+    fn cd_into_stream_should_fail() {
+        // arrange
+        let mut session = session(r"C:\work");
+
+        // act
+        let result = session.cd(Path::from_str(r"C:\work\notes.txt:s").unwrap());
+
+        // assert
+        assert!(result.is_err());
+        assert_eq!(session.current_dir(), &Path::from_str(r"C:\work\").unwrap());
+    }
+
+    #[rstest]
+    fn drive_cwds_are_remembered() {
+        // arrange
+        let mut session = session(r"C:\work");
+        session.cd(Path::from_str(r"D:\data\x").unwrap()).unwrap();
+
+        // act
+        let result = session
+            .cd(Path::from_str("C:src").unwrap())
+            .unwrap()
+            .clone();
+
+        // assert
+        assert_eq!(result, Path::from_str(r"C:\work\src\").unwrap());
+        assert_eq!(
+            session.drive_cwd('d'),
+            Path::from_str(r"D:\data\x\").unwrap()
+        );
+        assert_eq!(session.drive_cwd('E'), Path::from_str(r"E:\").unwrap());
+    }
+
+    #[rstest]
+    fn pushd_and_popd() {
+        // arrange
+        let mut session = session("/work");
+
+        // act
+        session.pushd(Path::from_str("a").unwrap()).unwrap();
+        session.pushd(Path::from_str("/tmp").unwrap()).unwrap();
+        let stack = session.stack().to_vec();
+        let first = session.popd().unwrap().clone();
+        let second = session.popd().unwrap().clone();
+        let third = session.popd();
+
+        // assert
+        assert_eq!(
+            stack,
+            [
+                Path::from_str("/work/").unwrap(),
+                Path::from_str("/work/a/").unwrap()
+            ]
+        );
+        assert_eq!(first, Path::from_str("/work/a/").unwrap());
+        assert_eq!(second, Path::from_str("/work/").unwrap());
+        assert!(third.is_err());
+    }
+
+    #[rstest]
+    #[case("/work", "a/../b/./c", "/work/b/c")]
+    #[case("/work", "~", "/home/me")]
+    #[case(r"C:\work", "D:x.txt", r"D:\x.txt")]
+    #[case(r"C:\work", r"\\?\C:\a\..\b", r"\\?\C:\b")]
+    fn resolve(#[case] cwd: &str, #[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let session = session(cwd);
+
+        // act
+        let resolved = session.resolve(Path::from_str(path).unwrap()).unwrap();
+
+        // assert
+        assert_eq!(resolved, Path::from_str(expected).unwrap());
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    fn cd_checked_against_the_file_system() {
+        // arrange
+        let dir = std::env::temp_dir();
+        let mut session = PathSession::new(Path::try_from(dir).unwrap())
+            .unwrap()
+            .with_check(DirCheck::FileSystem);
+        let before = session.current_dir().clone();
+
+        // act
+        let result = session.cd(Path::from_str("this/does/not/exist").unwrap());
+
+        // assert
+        assert!(result.is_err());
+        assert_eq!(session.current_dir(), &before);
+        assert!(session.cd(Path::from_str("..").unwrap()).is_ok());
+    }
+}
@@ -18,16 +18,16 @@ impl TryFrom<std::path::PathBuf> for Path {
     }
 }
 
-impl Into<StdPathBuf> for Path {
-    fn into(self) -> StdPathBuf {
-        let path_str = self.builder().build_string();
+impl From<Path> for StdPathBuf {
+    fn from(value: Path) -> Self {
+        let path_str = value.builder().build_string();
         StdPathBuf::from(path_str)
     }
 }
 
-impl Into<StdPathBuf> for &Path {
-    fn into(self) -> StdPathBuf {
-        let path_str = self.clone().builder().build_string();
+impl From<&Path> for StdPathBuf {
+    fn from(value: &Path) -> Self {
+        let path_str = value.clone().builder().build_string();
         StdPathBuf::from(path_str)
     }
 }
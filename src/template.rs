@@ -0,0 +1,574 @@
+//! Path templates with named placeholders, which capture values from paths and
+//! render paths from values.
+
+use core::{fmt, str::FromStr};
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{path::Path, pattern::raw_segments};
+
+/// A path with named placeholders, such as `src/{crate}/src/{module}.rs`.
+///
+/// A `{name}` placeholder matches a non-empty run of characters within one
+/// segment, and can share the segment with literal text. A `{name..}`
+/// placeholder must be a whole segment and matches one or more segments. When
+/// a placeholder could match more or less text, it matches as much as it can.
+/// A name used twice must capture the same value both times. A trailing
+/// separator only matches directories.
+///
+/// Templates are matched against the segments of a path, so `\` and `/` are
+/// both separators and any drive or root of the path is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    template: String,
+    segments: Vec<TemplateSegment>,
+    dir_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplateSegment {
+    Parts(Vec<Part>),
+    Rest(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Capture(String),
+}
+
+/// The values captured by the placeholders of a [`PathTemplate`], in the order
+/// they appear. A `{name..}` value holds its segments joined with `/`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Captures {
+    values: Vec<(String, String)>,
+}
+
+impl PathTemplate {
+    pub fn new(template: &str) -> Result<Self, &'static str> {
+        let dir_only = template.ends_with(['/', '\\']);
+
+        let segments = template
+            .split(['/', '\\'])
+            .filter(|segment| !segment.is_empty())
+            .map(parse_segment)
+            .collect::<Result<_, _>>()?;
+
+        Ok(PathTemplate {
+            template: template.to_string(),
+            segments,
+            dir_only,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Returns the names of the placeholders, in the order they first appear.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+
+        for segment in &self.segments {
+            let segment_names = match segment {
+                TemplateSegment::Rest(name) => alloc::vec![name.as_str()],
+                TemplateSegment::Parts(parts) => parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        Part::Capture(name) => Some(name.as_str()),
+                        Part::Literal(_) => None,
+                    })
+                    .collect(),
+            };
+
+            for name in segment_names {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        names
+    }
+
+    /// Returns the values of the placeholders if `path` matches the template.
+    pub fn match_path(&self, path: impl AsRef<Path>) -> Option<Captures> {
+        let path = path.as_ref();
+
+        if self.dir_only && !path.is_dir() {
+            return None;
+        }
+
+        let segments = raw_segments(path);
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let mut captures = Captures::new();
+
+        match_segments(&self.segments, &segments, &mut captures).then_some(captures)
+    }
+
+    /// Renders a relative path with each placeholder replaced by its value.
+    pub fn render(&self, captures: &Captures) -> Result<Path, &'static str> {
+        let mut path = Path::new();
+
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Rest(name) => {
+                    let value = captures
+                        .get(name)
+                        .ok_or("missing value for template placeholder")?;
+
+                    if value.split(['/', '\\']).any(str::is_empty) {
+                        return Err("template values can't have empty segments");
+                    }
+
+                    for segment in value.split(['/', '\\']) {
+                        path.segments.push(segment.to_string());
+                    }
+                }
+                TemplateSegment::Parts(parts) => {
+                    let mut rendered = String::new();
+
+                    for part in parts {
+                        match part {
+                            Part::Literal(literal) => rendered.push_str(literal),
+                            Part::Capture(name) => {
+                                let value = captures
+                                    .get(name)
+                                    .ok_or("missing value for template placeholder")?;
+
+                                if value.is_empty() {
+                                    return Err("template values can't have empty segments");
+                                }
+
+                                if value.contains(['/', '\\']) {
+                                    return Err("template value spans several segments");
+                                }
+
+                                rendered.push_str(value);
+                            }
+                        }
+                    }
+
+                    path.segments.push(rendered);
+                }
+            }
+        }
+
+        path.is_dir = self.dir_only;
+        Ok(path)
+    }
+
+    /// How specific the template is, for choosing between templates which
+    /// match the same path. Templates without a `{name..}` placeholder are
+    /// more specific, then those with more wholly literal segments, then more
+    /// literal text.
+    fn specificity(&self) -> (bool, usize, usize) {
+        let mut literal_segments = 0;
+        let mut literal_len = 0;
+        let mut spans = false;
+
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Rest(_) => spans = true,
+                TemplateSegment::Parts(parts) => {
+                    let mut literal = true;
+
+                    for part in parts {
+                        match part {
+                            Part::Literal(text) => literal_len += text.len(),
+                            Part::Capture(_) => literal = false,
+                        }
+                    }
+
+                    literal_segments += usize::from(literal);
+                }
+            }
+        }
+
+        (!spans, literal_segments, literal_len)
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PathTemplate::new(s)
+    }
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl Captures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of the placeholder `name`.
+    pub fn with(mut self, name: &str, value: &str) -> Self {
+        match self.values.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.values.push((name.to_string(), value.to_string())),
+        }
+
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Records `value` for `name`, or checks it against the value already
+    /// recorded.
+    fn bind(&mut self, name: &str, value: &str) -> bool {
+        match self.get(name) {
+            Some(existing) => existing == value,
+            None => {
+                self.values.push((name.to_string(), value.to_string()));
+                true
+            }
+        }
+    }
+}
+
+impl<'a> FromIterator<(&'a str, &'a str)> for Captures {
+    fn from_iter<I: IntoIterator<Item = (&'a str, &'a str)>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Captures::new(), |captures, (name, value)| {
+                captures.with(name, value)
+            })
+    }
+}
+
+/// Picks the most specific of several templates matching a path.
+#[derive(Debug, Clone)]
+pub struct TemplateRouter<T> {
+    routes: Vec<(PathTemplate, T)>,
+}
+
+impl<T> Default for TemplateRouter<T> {
+    fn default() -> Self {
+        TemplateRouter { routes: Vec::new() }
+    }
+}
+
+impl<T> TemplateRouter<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_route(mut self, template: PathTemplate, value: T) -> Self {
+        self.routes.push((template, value));
+        self
+    }
+
+    /// Returns the value and captures of the most specific template matching
+    /// `path`. Of equally specific templates, the first added wins.
+    pub fn route(&self, path: impl AsRef<Path>) -> Option<(&T, Captures)> {
+        let path = path.as_ref();
+        let mut best: Option<(&PathTemplate, &T, Captures)> = None;
+
+        for (template, value) in &self.routes {
+            let Some(captures) = template.match_path(path) else {
+                continue;
+            };
+
+            if best
+                .as_ref()
+                .is_none_or(|(b, _, _)| template.specificity() > b.specificity())
+            {
+                best = Some((template, value, captures));
+            }
+        }
+
+        best.map(|(_, value, captures)| (value, captures))
+    }
+}
+
+fn parse_segment(segment: &str) -> Result<TemplateSegment, &'static str> {
+    let mut parts = Vec::new();
+    let mut rest = segment;
+
+    while !rest.is_empty() {
+        match rest.find(['{', '}']) {
+            Some(index) if rest[index..].starts_with('}') => {
+                return Err("unmatched '}' in template");
+            }
+            Some(0) => {
+                let end = rest.find('}').ok_or("unclosed placeholder in template")?;
+                let name = &rest[1..end];
+
+                if let Some(name) = name.strip_suffix("..") {
+                    if end + 1 != rest.len() || !parts.is_empty() {
+                        return Err("a spanning placeholder must be a whole segment");
+                    }
+
+                    validate_name(name)?;
+                    return Ok(TemplateSegment::Rest(name.to_string()));
+                }
+
+                validate_name(name)?;
+
+                if let Some(Part::Capture(_)) = parts.last() {
+                    return Err("placeholders must be separated by literal text");
+                }
+
+                parts.push(Part::Capture(name.to_string()));
+                rest = &rest[end + 1..];
+            }
+            index => {
+                let index = index.unwrap_or(rest.len());
+                parts.push(Part::Literal(rest[..index].to_string()));
+                rest = &rest[index..];
+            }
+        }
+    }
+
+    Ok(TemplateSegment::Parts(parts))
+}
+
+fn validate_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err("invalid placeholder name in template");
+    }
+
+    Ok(())
+}
+
+fn match_segments(
+    template: &[TemplateSegment],
+    segments: &[&str],
+    captures: &mut Captures,
+) -> bool {
+    let Some((first, template_rest)) = template.split_first() else {
+        return segments.is_empty();
+    };
+
+    let bound = captures.len();
+
+    match first {
+        TemplateSegment::Rest(name) => {
+            for len in (1..=segments.len()).rev() {
+                if captures.bind(name, &segments[..len].join("/"))
+                    && match_segments(template_rest, &segments[len..], captures)
+                {
+                    return true;
+                }
+
+                captures.values.truncate(bound);
+            }
+
+            false
+        }
+        TemplateSegment::Parts(parts) => {
+            let Some((segment, segments_rest)) = segments.split_first() else {
+                return false;
+            };
+
+            // The segment's own captures may have to change for the rest of
+            // the path to match, so both are searched together.
+            match_parts(
+                parts,
+                segment,
+                &mut |captures| match_segments(template_rest, segments_rest, captures),
+                captures,
+            )
+        }
+    }
+}
+
+fn match_parts(
+    parts: &[Part],
+    text: &str,
+    then: &mut dyn FnMut(&mut Captures) -> bool,
+    captures: &mut Captures,
+) -> bool {
+    let Some((first, rest)) = parts.split_first() else {
+        return text.is_empty() && then(captures);
+    };
+
+    match first {
+        Part::Literal(literal) => text
+            .strip_prefix(literal.as_str())
+            .is_some_and(|text| match_parts(rest, text, then, captures)),
+        Part::Capture(name) => {
+            let bound = captures.len();
+
+            for end in (1..=text.len())
+                .rev()
+                .filter(|end| text.is_char_boundary(*end))
+            {
+                if captures.bind(name, &text[..end])
+                    && match_parts(rest, &text[end..], then, captures)
+                {
+                    return true;
+                }
+
+                captures.values.truncate(bound);
+            }
+
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("src/{crate}/src/{module}.rs", "src/canonic/src/path.rs", Some(vec![("crate", "canonic"), ("module", "path")]))]
+    #[case(
+        "src/{crate}/src/{module}.rs",
+        "C:\\src\\canonic\\src\\path.rs",
+        Some(vec![("crate", "canonic"), ("module", "path")])
+    )]
+    #[case("src/{crate}/src/{module}.rs", "src\\canonic\\src\\path.rs", Some(vec![("crate", "canonic"), ("module", "path")]))]
+    #[case("src/{crate}/src/{module}.rs", "/src/canonic/src/path.rs", Some(vec![("crate", "canonic"), ("module", "path")]))]
+    #[case("src/{crate}/src/{module}.rs", "src/canonic/src/path.txt", None)]
+    #[case("{name}.{ext}", "archive.tar.gz", Some(vec![("name", "archive.tar"), ("ext", "gz")]))]
+    #[case("docs/{rest..}", "docs/a/b/c.md", Some(vec![("rest", "a/b/c.md")]))]
+    #[case("docs/{rest..}", "docs", None)]
+    #[case("{dir..}/{file}.rs", "a/b/c.rs", Some(vec![("dir", "a/b"), ("file", "c")]))]
+    #[case("{dir..}/mod/{dir..}", "a/b/mod/a/b", Some(vec![("dir", "a/b")]))]
+    #[case("{dir..}/mod/{dir..}", "a/b/mod/a", None)]
+    #[case("{x}/{x}", "a/a", Some(vec![("x", "a")]))]
+    #[case("{x}/{x}", "a/b", None)]
+    #[case("out/{name}/", "out/a", None)]
+    #[case("out/{name}/", "out/a/", Some(vec![("name", "a")]))]
+    #[case("lib-{name}", "lib-", None)]
+    fn match_path(
+        #[case] template: &str,
+        #[case] path: &str,
+        #[case] expected: Option<Vec<(&str, &str)>>,
+    ) {
+        // arrange
+        let template = PathTemplate::new(template).unwrap();
+
+        // act
+        let captures = template.match_path(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(
+            captures,
+            expected.map(|values| values.into_iter().collect())
+        );
+    }
+
+    #[rstest]
+    #[case("src/{crate}/src/{module}.rs", vec![("crate", "canonic"), ("module", "path")], Ok("src/canonic/src/path.rs"))]
+    #[case("docs/{rest..}", vec![("rest", "a/b/c.md")], Ok("docs/a/b/c.md"))]
+    #[case("out/{name}/", vec![("name", "a")], Ok("out/a/"))]
+    #[case("src/{module}.rs", vec![], Err("missing value for template placeholder"))]
+    #[case("src/{module}.rs", vec![("module", "a/b")], Err("template value spans several segments"))]
+    #[case("src/{module}.rs", vec![("module", "")], Err("template values can't have empty segments"))]
+    #[case("docs/{rest..}", vec![("rest", "a//b")], Err("template values can't have empty segments"))]
+    fn render(
+        #[case] template: &str,
+        #[case] values: Vec<(&str, &str)>,
+        #[case] expected: Result<&str, &str>,
+    ) {
+        // arrange
+        let template = PathTemplate::new(template).unwrap();
+        let captures = values.into_iter().collect();
+
+        // act
+        let path = template.render(&captures);
+
+        // assert
+        assert_eq!(path, expected.map(|e| Path::from_str(e).unwrap()));
+    }
+
+    #[rstest]
+    #[case("src/{crate}/src/{module}.rs", "src/canonic/src/path.rs")]
+    #[case("{dir..}/{name}.{ext}", "a/b/c.tar.gz")]
+    fn round_trip(#[case] template: &str, #[case] path: &str) {
+        // arrange
+        let template = PathTemplate::new(template).unwrap();
+
+        // act
+        let rendered =
+            template.render(&template.match_path(Path::from_str(path).unwrap()).unwrap());
+
+        // assert
+        assert_eq!(rendered, Ok(Path::from_str(path).unwrap()));
+    }
+
+    #[rstest]
+    #[case("a/{b", "unclosed placeholder in template")]
+    #[case("a/b}", "unmatched '}' in template")]
+    #[case("{}", "invalid placeholder name in template")]
+    #[case("{a-b}", "invalid placeholder name in template")]
+    #[case("{a}{b}", "placeholders must be separated by literal text")]
+    #[case("x{rest..}", "a spanning placeholder must be a whole segment")]
+    #[case("{rest..}x", "a spanning placeholder must be a whole segment")]
+    fn invalid(#[case] template: &str, #[case] expected: &str) {
+        // act
+        let result = PathTemplate::new(template);
+
+        // assert
+        assert_eq!(result, Err(expected));
+    }
+
+    #[rstest]
+    fn names() {
+        // arrange
+        let template = PathTemplate::new("{dir..}/{name}.{ext}/{name}").unwrap();
+
+        // act
+        let names = template.names();
+
+        // assert
+        assert_eq!(names, vec!["dir", "name", "ext"]);
+    }
+
+    #[rstest]
+    #[case("src/lib.rs", Some(("lib", vec![])))]
+    #[case("src/path.rs", Some(("module", vec![("module", "path")])))]
+    #[case("src/a/b.rs", Some(("nested", vec![("dir", "a"), ("module", "b")])))]
+    #[case("src/a/b/c.rs", Some(("any", vec![("rest", "a/b/c.rs")])))]
+    #[case("tests/a.rs", None)]
+    fn route(#[case] path: &str, #[case] expected: Option<(&str, Vec<(&str, &str)>)>) {
+        // arrange
+        let t = |s: &str| PathTemplate::new(s).unwrap();
+        let router = TemplateRouter::new()
+            .with_route(t("src/{rest..}"), "any")
+            .with_route(t("src/{module}.rs"), "module")
+            .with_route(t("src/{dir}/{module}.rs"), "nested")
+            .with_route(t("src/lib.rs"), "lib");
+
+        // act
+        let route = router.route(Path::from_str(path).unwrap());
+
+        // assert
+        assert_eq!(
+            route.map(|(name, captures)| (*name, captures)),
+            expected.map(|(name, values)| (name, values.into_iter().collect()))
+        );
+    }
+}
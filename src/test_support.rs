@@ -0,0 +1,78 @@
+//! Fixtures shared by the tests of several modules.
+
+use std::{
+    path::{Path as StdPath, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::path::Path;
+
+/// A fresh directory in the temp directory, which is removed along with
+/// everything in it when dropped.
+pub(crate) struct TempTree {
+    root: PathBuf,
+}
+
+impl TempTree {
+    /// Creates an empty tree whose directory name starts with `name`.
+    pub(crate) fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let root = std::env::temp_dir().join(format!(
+            "canonic-{name}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+
+        TempTree {
+            root: root.canonicalize().unwrap(),
+        }
+    }
+
+    /// Creates the directory at `path` in the tree, along with its parents.
+    pub(crate) fn with_dir(self, path: &str) -> Self {
+        std::fs::create_dir_all(self.join(path)).unwrap();
+        self
+    }
+
+    /// Creates the file at `path` in the tree, along with its parents.
+    pub(crate) fn with_file(self, path: &str, contents: &str) -> Self {
+        let file = self.join(path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, contents).unwrap();
+        self
+    }
+
+    /// Creates a symlink at `link` in the tree pointing at `target`.
+    #[cfg(unix)]
+    pub(crate) fn with_symlink(self, target: impl AsRef<StdPath>, link: &str) -> Self {
+        std::os::unix::fs::symlink(target, self.join(link)).unwrap();
+        self
+    }
+
+    /// The canonical path of the tree's directory.
+    pub(crate) fn root(&self) -> &StdPath {
+        &self.root
+    }
+
+    /// The canonical path of the tree's directory, as a directory [`Path`].
+    pub(crate) fn path(&self) -> Path {
+        let mut path = Path::try_from(self.root.as_path()).unwrap();
+        path.is_dir = true;
+        path
+    }
+
+    /// The host path of `path` in the tree.
+    pub(crate) fn join(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+impl Drop for TempTree {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,113 @@
+use core::str::FromStr;
+
+use alloc::{collections::BTreeMap, string::String};
+
+use crate::path::Path;
+
+/// Looks up the home directories of users, used to expand `~user` segments.
+pub trait UserDatabase {
+    fn user_home_dir(&self, user: &str) -> Option<Path>;
+}
+
+impl UserDatabase for BTreeMap<String, Path> {
+    fn user_home_dir(&self, user: &str) -> Option<Path> {
+        self.get(user).cloned()
+    }
+}
+
+impl<T: UserDatabase + ?Sized> UserDatabase for &T {
+    fn user_home_dir(&self, user: &str) -> Option<Path> {
+        (**self).user_home_dir(user)
+    }
+}
+
+/// A [`UserDatabase`] backed by a `passwd(5)` file, `/etc/passwd` by default.
+/// The file is read on every lookup, and a missing or unreadable file simply
+/// knows no users.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passwd {
+    file: std::path::PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl Passwd {
+    pub fn new(file: impl Into<std::path::PathBuf>) -> Self {
+        Passwd { file: file.into() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Passwd {
+    fn default() -> Self {
+        Passwd::new("/etc/passwd")
+    }
+}
+
+#[cfg(feature = "std")]
+impl UserDatabase for Passwd {
+    fn user_home_dir(&self, user: &str) -> Option<Path> {
+        let contents = std::fs::read_to_string(&self.file).ok()?;
+        passwd_home_dir(&contents, user)
+    }
+}
+
+/// Finds the home directory of `user` in the contents of a passwd file, where
+/// each line reads `name:password:uid:gid:gecos:home:shell`.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) fn passwd_home_dir(contents: &str, user: &str) -> Option<Path> {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.split(':'))
+        .find_map(|mut fields| {
+            if fields.next()? != user {
+                return None;
+            }
+
+            Path::from_str(fields.nth(4)?).ok()
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    const PASSWD: &str = "\
+# local users
+root:x:0:0:root:/root:/bin/bash
+alice:x:1000:1000:Alice,,,:/home/alice:/bin/zsh
+bob:x:1001:1001::/srv/bob/:/usr/bin/fish
+broken:x:1002
+";
+
+    #[rstest]
+    #[case("root", Some("/root"))]
+    #[case("alice", Some("/home/alice"))]
+    #[case("bob", Some("/srv/bob/"))]
+    #[case("broken", None)]
+    #[case("carol", None)]
+    #[case("# local users", None)]
+    fn passwd_home_dir(#[case] user: &str, #[case] expected: Option<&str>) {
+        // act
+        let home = super::passwd_home_dir(PASSWD, user);
+
+        // assert
+        assert_eq!(home, expected.map(|e| Path::from_str(e).unwrap()));
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    fn passwd_missing_file() {
+        // arrange
+        let passwd = Passwd::new("/this/file/does/not/exist");
+
+        // act
+        let home = passwd.user_home_dir("root");
+
+        // assert
+        assert!(home.is_none());
+    }
+}
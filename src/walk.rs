@@ -0,0 +1,412 @@
+//! Recursive directory walking which yields canonic paths.
+
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+
+use crate::{
+    filesystem::{DirEntry, FileSystem, FileType, FsError},
+    path::Path,
+};
+
+type Prune = Box<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Walks the tree beneath a directory depth first.
+///
+/// Each entry is yielded relative to the root, with `is_dir` set from its file
+/// type. The root itself isn't yielded: its children are at depth 1.
+pub struct Walker<F> {
+    root: Path,
+    fs: F,
+    min_depth: usize,
+    max_depth: usize,
+    follow_symlinks: bool,
+    sorted: bool,
+    prune: Option<Prune>,
+}
+
+#[cfg(feature = "std")]
+impl Walker<crate::filesystem::StdFs> {
+    /// Walks `root` on the host filesystem.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self::new_in(root, crate::filesystem::StdFs)
+    }
+}
+
+impl<F: FileSystem> Walker<F> {
+    /// Walks `root` in `fs`.
+    pub fn new_in(root: impl AsRef<Path>, fs: F) -> Self {
+        let mut root = root.as_ref().clone();
+        root.is_dir = true;
+
+        Walker {
+            root,
+            fs,
+            min_depth: 1,
+            max_depth: usize::MAX,
+            follow_symlinks: false,
+            sorted: false,
+            prune: None,
+        }
+    }
+
+    /// Skips entries shallower than `depth`, while still descending into them.
+    pub fn with_min_depth(mut self, depth: usize) -> Self {
+        self.min_depth = depth.max(1);
+        self
+    }
+
+    /// Doesn't descend below `depth`. A depth of 1 lists only the root.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Descends into symlinks to directories. A symlink back to a directory
+    /// being walked is reported as [`FsError::Loop`] instead.
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Yields the entries of each directory sorted by name, rather than in the
+    /// order the filesystem lists them.
+    pub fn with_sorted(mut self, sorted: bool) -> Self {
+        self.sorted = sorted;
+        self
+    }
+
+    /// Skips the entries `prune` returns true for, without descending into
+    /// them. It's given the path relative to the root.
+    pub fn with_prune(mut self, prune: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        self.prune = Some(Box::new(prune));
+        self
+    }
+
+    fn root_visit(&self) -> Visit {
+        let canonical = if self.follow_symlinks {
+            self.fs.canonicalize(&self.root).ok()
+        } else {
+            None
+        };
+
+        Visit {
+            path: self.root.clone(),
+            relative: self.root.clone(),
+            ancestors: canonical.into_iter().collect(),
+            depth: 0,
+        }
+    }
+
+    fn descends(&self, visit: &Visit) -> bool {
+        visit.path.is_dir && visit.depth < self.max_depth
+    }
+
+    fn children(&self, dir: &Visit) -> Vec<Result<Visit, FsError>> {
+        let mut entries = match self.fs.read_dir(&dir.path) {
+            Ok(entries) => entries,
+            Err(e) => return vec![Err(e)],
+        };
+
+        if self.sorted {
+            entries.sort_by(|l, r| l.name().cmp(r.name()));
+        }
+
+        entries
+            .into_iter()
+            .filter_map(|entry| self.child(dir, entry).transpose())
+            .collect()
+    }
+
+    fn child(&self, dir: &Visit, entry: DirEntry) -> Result<Option<Visit>, FsError> {
+        let mut path = dir.path.clone();
+        path.segments.push(entry.name().to_string());
+        path.is_dir = entry.file_type() == FileType::Dir;
+
+        let target = match entry.file_type() {
+            FileType::Symlink if self.follow_symlinks => self
+                .fs
+                .canonicalize(&path)
+                .ok()
+                .filter(|target| target.is_dir),
+            _ => None,
+        };
+
+        path.is_dir |= target.is_some();
+
+        let relative = path.diff(&self.root).unwrap_or_else(|| path.clone());
+
+        if self.prune.as_ref().is_some_and(|prune| prune(&relative)) {
+            return Ok(None);
+        }
+
+        let mut ancestors = Vec::new();
+
+        if path.is_dir && self.follow_symlinks {
+            let canonical = match target {
+                Some(target) => target,
+                None => match dir.ancestors.last() {
+                    Some(parent) => {
+                        let mut canonical = parent.clone();
+                        canonical.segments.push(entry.name().to_string());
+                        canonical
+                    }
+                    None => self.fs.canonicalize(&path)?,
+                },
+            };
+
+            if dir.ancestors.contains(&canonical) {
+                return Err(FsError::Loop(path.rendered()));
+            }
+
+            ancestors = dir.ancestors.clone();
+            ancestors.push(canonical);
+        }
+
+        Ok(Some(Visit {
+            path,
+            relative,
+            ancestors,
+            depth: dir.depth + 1,
+        }))
+    }
+
+    /// Walks the tree with `threads` threads reading directories at once, and
+    /// collects the entries.
+    ///
+    /// If the walker is sorted the entries come back in the same order as
+    /// from the iterator, followed by any errors. Otherwise their order is
+    /// unspecified.
+    #[cfg(feature = "std")]
+    pub fn collect_parallel(self, threads: usize) -> Vec<Result<Path, FsError>>
+    where
+        F: Sync,
+    {
+        let mut results = Vec::new();
+        let mut level = vec![self.root_visit()];
+
+        while !level.is_empty() {
+            let dirs: Vec<Visit> = level.into_iter().filter(|v| self.descends(v)).collect();
+            let chunk_size = dirs.len().div_ceil(threads.max(1)).max(1);
+
+            let children: Vec<Vec<Result<Visit, FsError>>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = dirs
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(|| chunk.iter().flat_map(|dir| self.children(dir)).collect())
+                    })
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("walker thread panicked"))
+                    .collect()
+            });
+
+            level = Vec::new();
+
+            for child in children.into_iter().flatten() {
+                match child {
+                    Ok(visit) => {
+                        if visit.depth >= self.min_depth {
+                            results.push(Ok(visit.relative.clone()));
+                        }
+
+                        level.push(visit);
+                    }
+                    Err(e) => results.push(Err(e)),
+                }
+            }
+        }
+
+        if self.sorted {
+            results.sort_by(|l, r| match (l, r) {
+                (Ok(l), Ok(r)) => l
+                    .segments
+                    .iter()
+                    .map(|s| &s.0)
+                    .cmp(r.segments.iter().map(|s| &s.0)),
+                (Ok(_), Err(_)) => core::cmp::Ordering::Less,
+                (Err(_), Ok(_)) => core::cmp::Ordering::Greater,
+                (Err(_), Err(_)) => core::cmp::Ordering::Equal,
+            });
+        }
+
+        results
+    }
+}
+
+impl<F: FileSystem> IntoIterator for Walker<F> {
+    type Item = Result<Path, FsError>;
+    type IntoIter = Walk<F>;
+
+    fn into_iter(self) -> Walk<F> {
+        let stack = vec![Ok(self.root_visit())];
+
+        Walk {
+            walker: self,
+            stack,
+        }
+    }
+}
+
+struct Visit {
+    path: Path,
+    relative: Path,
+    /// The canonical paths of the directories from the root down to this one,
+    /// when symlinks are followed.
+    ancestors: Vec<Path>,
+    depth: usize,
+}
+
+/// The iterator over the entries of a [`Walker`].
+pub struct Walk<F> {
+    walker: Walker<F>,
+    stack: Vec<Result<Visit, FsError>>,
+}
+
+impl<F: FileSystem> Iterator for Walk<F> {
+    type Item = Result<Path, FsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let visit = match self.stack.pop()? {
+                Ok(visit) => visit,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if self.walker.descends(&visit) {
+                let children = self.walker.children(&visit);
+                self.stack.extend(children.into_iter().rev());
+            }
+
+            if visit.depth >= self.walker.min_depth {
+                return Some(Ok(visit.relative));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use alloc::{string::String, vec::Vec};
+    use rstest::rstest;
+
+    use super::*;
+    use crate::filesystem::MemoryFs;
+
+    /// ```text
+    /// /r/a/b/file.txt
+    /// /r/a/c.txt
+    /// /r/d/
+    /// /r/link -> a
+    /// /r/a/b/up -> ../..
+    /// ```
+    fn fs() -> MemoryFs {
+        let p = |s: &str| Path::from_str(s).unwrap();
+
+        MemoryFs::new()
+            .with_file(p("/r/a/b/file.txt"), 0)
+            .and_then(|fs| fs.with_file(p("/r/a/c.txt"), 0))
+            .and_then(|fs| fs.with_dir(p("/r/d")))
+            .and_then(|fs| fs.with_symlink(p("/r/link"), p("a")))
+            .and_then(|fs| fs.with_symlink(p("/r/a/b/up"), p("../..")))
+            .unwrap()
+    }
+
+    fn rendered(entries: impl IntoIterator<Item = Result<Path, FsError>>) -> Vec<String> {
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                Ok(path) => path.rendered(),
+                Err(e) => alloc::format!("error: {e}"),
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(Walker::new_in(Path::from_str("/r").unwrap(), fs()), vec![
+        "a/", "a/b/", "a/b/file.txt", "a/b/up", "a/c.txt", "d/", "link",
+    ])]
+    #[case(Walker::new_in(Path::from_str("/r").unwrap(), fs()).with_max_depth(1), vec![
+        "a/", "d/", "link",
+    ])]
+    #[case(Walker::new_in(Path::from_str("/r").unwrap(), fs()).with_min_depth(3), vec![
+        "a/b/file.txt", "a/b/up",
+    ])]
+    #[case(
+        Walker::new_in(Path::from_str("/r").unwrap(), fs())
+            .with_prune(|path| path.basename() == Some("b")),
+        vec!["a/", "a/c.txt", "d/", "link"],
+    )]
+    #[case(
+        Walker::new_in(Path::from_str("/r").unwrap(), fs())
+            .with_follow_symlinks(true)
+            .with_prune(|path| path.basename() == Some("a")),
+        vec!["d/", "link/", "link/b/", "link/b/file.txt", "error: /r/link/b/up/: too many levels of symbolic links", "link/c.txt"],
+    )]
+    fn walk(#[case] walker: Walker<MemoryFs>, #[case] expected: Vec<&str>) {
+        // act
+        let entries = rendered(walker.with_sorted(true));
+
+        // assert
+        assert_eq!(entries, expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    fn collect_parallel() {
+        // arrange
+        let walker = || {
+            Walker::new_in(Path::from_str("/r").unwrap(), fs())
+                .with_follow_symlinks(true)
+                .with_sorted(true)
+        };
+
+        // act
+        let parallel = rendered(walker().collect_parallel(4));
+
+        // assert
+        let (entries, mut errors): (Vec<_>, Vec<_>) = rendered(walker())
+            .into_iter()
+            .partition(|e| !e.starts_with("error"));
+        let split = parallel.len() - errors.len();
+        let mut parallel_errors = parallel[split..].to_vec();
+        parallel_errors.sort();
+        errors.sort();
+        assert_eq!(parallel[..split], entries);
+        assert_eq!(parallel_errors, errors);
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[rstest]
+    fn walk_std() {
+        use crate::test_support::TempTree;
+
+        // arrange
+        let tree = TempTree::new("walk")
+            .with_dir("a/b")
+            .with_file("a/file.txt", "")
+            .with_symlink("a", "link");
+
+        // act
+        let entries = rendered(
+            Walker::new(tree.path())
+                .with_follow_symlinks(true)
+                .with_sorted(true),
+        );
+
+        // assert
+        assert_eq!(
+            entries,
+            vec![
+                "a/",
+                "a/b/",
+                "a/file.txt",
+                "link/",
+                "link/b/",
+                "link/file.txt"
+            ]
+        );
+    }
+}
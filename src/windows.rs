@@ -0,0 +1,532 @@
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str::FromStr;
+
+use crate::{
+    packed_list::PathSegmentList,
+    path::{Drive, Path, Prefix, RESERVED_NAMES, Root},
+};
+
+/// The working directory state of a Windows process: one current directory per
+/// drive letter, plus the drive which is currently selected.
+///
+/// This is plain data and never consults the host, so it can be used to resolve
+/// Windows paths on any platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowsCwdState {
+    current_drive: char,
+    cwds: BTreeMap<char, Path>,
+}
+
+impl WindowsCwdState {
+    /// Creates a new state whose current directory is `cwd`. The drive of `cwd`
+    /// becomes the current drive.
+    pub fn new(cwd: impl AsRef<Path>) -> Result<Self, &'static str> {
+        let cwd = drive_rooted(cwd.as_ref())?;
+        let letter = drive_key(&cwd);
+
+        let mut cwds = BTreeMap::new();
+        cwds.insert(letter, cwd);
+
+        Ok(WindowsCwdState {
+            current_drive: letter,
+            cwds,
+        })
+    }
+
+    pub fn current_drive(&self) -> char {
+        self.current_drive
+    }
+
+    pub fn current_dir(&self) -> Path {
+        self.drive_cwd(self.current_drive)
+    }
+
+    /// Returns the current directory of the given drive. Drives which were never
+    /// visited default to their root, as they do on Windows.
+    pub fn drive_cwd(&self, letter: char) -> Path {
+        let letter = letter.to_ascii_uppercase();
+
+        match self.cwds.get(&letter) {
+            Some(cwd) => cwd.clone(),
+            None => Path {
+                drive: Some(Drive { letter }),
+                root: Some(Root::Normal),
+                is_dir: true,
+                ..Path::new()
+            },
+        }
+    }
+
+    /// Records `cwd` as the current directory of its drive without switching the
+    /// current drive.
+    pub fn set_drive_cwd(&mut self, cwd: impl AsRef<Path>) -> Result<(), &'static str> {
+        let cwd = drive_rooted(cwd.as_ref())?;
+        self.cwds.insert(drive_key(&cwd), cwd);
+        Ok(())
+    }
+
+    pub fn set_current_drive(&mut self, letter: char) -> Result<(), &'static str> {
+        if !letter.is_ascii_alphabetic() {
+            return Err("drive letters must be ascii letters");
+        }
+
+        self.current_drive = letter.to_ascii_uppercase();
+        Ok(())
+    }
+
+    /// Changes directory the way `SetCurrentDirectory` does: `path` is made
+    /// absolute against this state, then becomes the cwd of its drive and that
+    /// drive becomes current.
+    pub fn set_current_dir(&mut self, path: impl AsRef<Path>) -> Result<(), &'static str> {
+        let cwd = self.absolutize(path)?.resolve()?;
+        let cwd = drive_rooted(&cwd)?;
+        self.current_drive = drive_key(&cwd);
+        self.cwds.insert(self.current_drive, cwd);
+        Ok(())
+    }
+
+    /// Makes `path` absolute the way Windows does.
+    ///
+    /// - `C:foo` is joined onto the current directory of drive `C`.
+    /// - `\foo` is rooted on the current drive.
+    /// - `foo` is joined onto the current directory.
+    /// - Fully qualified, UNC and `\\?\` or `\\.\` paths are returned unchanged.
+    ///
+    /// The result is not resolved, so `.` and `..` segments are kept.
+    pub fn absolutize(&self, path: impl AsRef<Path>) -> Result<Path, &'static str> {
+        let path = path.as_ref();
+
+        if path.prefix.is_some() || path.root == Some(Root::Unc) {
+            return Ok(path.clone());
+        }
+
+        match (&path.drive, &path.root) {
+            (Some(_), Some(_)) => Ok(path.clone()),
+            (Some(Drive { letter }), None) => {
+                let mut relative = path.clone();
+                relative.drive = None;
+                self.drive_cwd(*letter).join(relative)
+            }
+            (None, Some(_)) => {
+                let mut result = path.clone();
+                result.drive = Some(Drive {
+                    letter: self.current_drive,
+                });
+                Ok(result)
+            }
+            (None, None) => self.current_dir().join(path),
+        }
+    }
+}
+
+/// Parses `path` and normalizes it the way Win32 `GetFullPathNameW` does:
+///
+/// - `/` is treated as `\` and runs of separators are collapsed, except for the
+///   leading pair of a UNC or device path.
+/// - Relative, drive-relative and rooted paths are made absolute against `state`.
+/// - `.` segments are removed and `..` removes the previous segment, but never
+///   climbs above the root. The root of a UNC path includes the server and
+///   share, and the root of a `\\.\` path includes its first segment.
+/// - A segment ending in a single period loses that period. If the path doesn't
+///   end in a separator, all trailing periods and spaces are removed from the
+///   final segment.
+/// - If the final segment names a legacy DOS device such as `NUL` or `COM1`,
+///   ignoring any extension and trailing spaces, the result is that device, as
+///   in `\\.\NUL`. UNC and `\\.\` paths are exempt.
+/// - `\\?\` paths are returned as they are.
+pub fn full_path_name(path: &str, state: &WindowsCwdState) -> Result<Path, &'static str> {
+    let path: String = path
+        .chars()
+        .map(|c| if c == '/' { '\\' } else { c })
+        .collect();
+
+    if path.starts_with(r"\\?\") {
+        return Path::from_str(&path);
+    }
+
+    let leading = path.chars().take_while(|c| *c == '\\').count().min(2);
+    let mut collapsed = String::with_capacity(path.len());
+
+    for _ in 0..leading {
+        collapsed.push('\\');
+    }
+
+    for c in path.chars().skip_while(|c| *c == '\\') {
+        if c != '\\' || !collapsed.ends_with('\\') {
+            collapsed.push(c);
+        }
+    }
+
+    let trailing_separator = collapsed.ends_with('\\');
+    normalize(&Path::from_str(&collapsed)?, state, trailing_separator)
+}
+
+pub(crate) fn full_path(path: &Path, state: &WindowsCwdState) -> Result<Path, &'static str> {
+    // the parser marks paths ending in a period as directories, so a trailing
+    // separator can only be inferred when the final segment doesn't end in one
+    let trailing_separator = path.is_dir
+        && path
+            .segments
+            .iter()
+            .next_back()
+            .is_none_or(|s| !s.0.ends_with('.'));
+
+    normalize(path, state, trailing_separator)
+}
+
+fn normalize(
+    path: &Path,
+    state: &WindowsCwdState,
+    trailing_separator: bool,
+) -> Result<Path, &'static str> {
+    if path.prefix == Some(Prefix::ExtendedPath) {
+        return Ok(path.clone());
+    }
+
+    if !trailing_separator && let Some(device) = dos_device_name(path) {
+        return Ok(Path {
+            prefix: Some(Prefix::Device),
+            root: Some(Root::Normal),
+            segments: PathSegmentList::from(device.to_string()),
+            ..Path::new()
+        });
+    }
+
+    let (mut path, fixed) = match (&path.prefix, &path.root, &path.drive) {
+        (Some(Prefix::Device), _, Some(_)) => (path.clone(), 0),
+        (Some(Prefix::Device), _, None) => (path.clone(), 1),
+        (None, Some(Root::Unc), _) => (path.clone(), 2),
+        _ => (state.absolutize(path)?, 0),
+    };
+
+    let last = path.segments.iter().next_back().map(|s| s.0.clone());
+    let mut segments: Vec<String> = Vec::with_capacity(path.segments.len());
+
+    for (i, segment) in path.segments.iter().enumerate() {
+        let segment = segment.0.as_str();
+
+        if i < fixed {
+            segments.push(segment.to_string());
+            continue;
+        }
+
+        match segment {
+            "." => {}
+            ".." => {
+                if segments.len() > fixed {
+                    segments.pop();
+                }
+            }
+            segment if segment.ends_with('.') && !segment.ends_with("..") => {
+                segments.push(segment[..segment.len() - 1].to_string());
+            }
+            segment => segments.push(segment.to_string()),
+        }
+    }
+
+    let ends_in_name = last.is_some_and(|s| s != "." && s != "..");
+
+    if !trailing_separator
+        && ends_in_name
+        && segments.len() > fixed
+        && let Some(segment) = segments.last_mut()
+    {
+        segment.truncate(segment.trim_end_matches(['.', ' ']).len());
+
+        if segment.is_empty() {
+            segments.pop();
+            path.is_dir = true;
+        }
+    }
+
+    path.segments = segments.into_iter().collect();
+    Ok(path)
+}
+
+/// Returns the device name if the final segment of `path` refers to a legacy
+/// DOS device, which Win32 resolves regardless of the directory it appears in.
+fn dos_device_name(path: &Path) -> Option<&str> {
+    if path.prefix.is_some() || path.root == Some(Root::Unc) {
+        return None;
+    }
+
+    dos_device(&path.segments.iter().next_back()?.0)
+}
+
+/// Returns the device name if `segment` names a legacy DOS device, ignoring any
+/// extension and trailing spaces, as `nul.txt` and `COM1 ` do.
+pub(crate) fn dos_device(segment: &str) -> Option<&str> {
+    let name = segment[..segment.find('.').unwrap_or(segment.len())].trim_end_matches(' ');
+
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name))
+        .then_some(name)
+}
+
+/// Returns the drive path which a unix path names when drives are mounted
+/// under `mount`, so `/mnt/c/Users` is `C:\Users` when `mount` is `/mnt`.
+pub(crate) fn mounted_drive_path(path: &Path, mount: &Path) -> Option<Path> {
+    let mut segments = path.segments.iter();
+
+    for mount_segment in mount.segments.iter() {
+        if segments.next()? != mount_segment {
+            return None;
+        }
+    }
+
+    let letter = drive_letter(&segments.next()?.0)?;
+    let rest: PathSegmentList = segments.cloned().collect();
+
+    Some(Path {
+        drive: Some(Drive {
+            letter: letter.to_ascii_uppercase(),
+        }),
+        root: Some(Root::Normal),
+        is_dir: path.is_dir || rest.len() == 0,
+        segments: rest,
+        stream: path.stream.clone(),
+        ..Path::new()
+    })
+}
+
+/// Returns the unix path of the drive path `path` when drives are mounted under
+/// `mount`, the inverse of [`mounted_drive_path`].
+pub(crate) fn drive_mount_path(path: &Path, letter: char, mount: &Path) -> Path {
+    let mut result = mount.clone();
+    result
+        .segments
+        .push(letter.to_ascii_lowercase().to_string());
+
+    for segment in path.segments.iter() {
+        result.segments.push(segment.clone());
+    }
+
+    result.stream = path.stream.clone();
+    result.is_dir = path.is_dir();
+    result
+}
+
+/// Returns the letter if `segment` is a single letter, as drives are named in
+/// mount points.
+pub(crate) fn drive_letter(segment: &str) -> Option<char> {
+    let mut chars = segment.chars();
+    let letter = chars.next()?;
+
+    (letter.is_ascii_alphabetic() && chars.next().is_none()).then_some(letter)
+}
+
+fn drive_rooted(path: &Path) -> Result<Path, &'static str> {
+    if path.prefix.is_some() || path.drive.is_none() || path.root != Some(Root::Normal) {
+        return Err("windows working directories must be drive-rooted paths");
+    }
+
+    let mut path = path.clone();
+    path.stream = None;
+    path.is_dir = true;
+    Ok(path)
+}
+
+fn drive_key(path: &Path) -> char {
+    path.drive
+        .as_ref()
+        .expect("path is drive-rooted")
+        .letter
+        .to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn state() -> WindowsCwdState {
+        let mut state = WindowsCwdState::new(Path::from_str(r"C:\Users\me").unwrap()).unwrap();
+        state
+            .set_drive_cwd(Path::from_str(r"D:\src\app").unwrap())
+            .unwrap();
+        state
+    }
+
+    #[rstest]
+    #[case(r"foo\bar", r"C:\Users\me\foo\bar")]
+    #[case(r"C:foo", r"C:\Users\me\foo")]
+    #[case(r"c:foo", r"C:\Users\me\foo")]
+    #[case(r"D:foo", r"D:\src\app\foo")]
+    #[case(r"E:foo", r"E:\foo")]
+    #[case(r"D:", r"D:\src\app\")]
+    #[case(r"\foo", r"C:\foo")]
+    #[case(r"\", r"C:\")]
+    #[case(r"..\foo", r"C:\Users\me\..\foo")]
+    #[case(r"D:\x", r"D:\x")]
+    #[case(r"\\server\share\x", r"\\server\share\x")]
+    #[case(r"\\?\D:\x", r"\\?\D:\x")]
+    #[case(r"\\.\COM1", r"\\.\COM1")]
+    fn absolutize(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let state = state();
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = state.absolutize(&path).unwrap();
+
+        // assert
+        assert_eq!(result, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    fn absolutize_after_switching_drive() {
+        // arrange
+        let mut state = state();
+
+        // act
+        state.set_current_drive('d').unwrap();
+
+        // assert
+        let path = Path::from_str(r"\foo").unwrap();
+        assert_eq!(
+            state.absolutize(&path).unwrap(),
+            Path::from_str(r"D:\foo").unwrap()
+        );
+
+        let path = Path::from_str("foo").unwrap();
+        assert_eq!(
+            state.absolutize(&path).unwrap(),
+            Path::from_str(r"D:\src\app\foo").unwrap()
+        );
+    }
+
+    #[rstest]
+    fn set_current_dir_remembers_each_drive() {
+        // arrange
+        let mut state = state();
+
+        // act
+        state
+            .set_current_dir(Path::from_str(r"D:..").unwrap())
+            .unwrap();
+        state
+            .set_current_dir(Path::from_str(r"C:tmp").unwrap())
+            .unwrap();
+
+        // assert
+        assert_eq!(state.current_drive(), 'C');
+        assert_eq!(
+            state.current_dir(),
+            Path::from_str(r"C:\Users\me\tmp\").unwrap()
+        );
+        assert_eq!(state.drive_cwd('D'), Path::from_str(r"D:\src\").unwrap());
+    }
+
+    // documented GetFullPathNameW behaviors, with a cwd of C:\Users\me and a cwd
+    // of D:\src\app on drive D
+    #[rstest]
+    // relative, drive-relative and rooted paths
+    #[case(r"foo", r"C:\Users\me\foo")]
+    #[case(r"..\foo", r"C:\Users\foo")]
+    #[case(r".", r"C:\Users\me")]
+    #[case(r"\foo", r"C:\foo")]
+    #[case(r"C:", r"C:\Users\me")]
+    #[case(r"D:foo", r"D:\src\app\foo")]
+    #[case(r"D:..\..\..\foo", r"D:\foo")]
+    #[case(r"E:foo", r"E:\foo")]
+    // separators
+    #[case(r"C:/a/b", r"C:\a\b")]
+    #[case(r"C:\a\\\b", r"C:\a\b")]
+    #[case(r"C:\a//\b\\", r"C:\a\b")]
+    #[case(r"a\\b", r"C:\Users\me\a\b")]
+    // relative segments
+    #[case(r"C:\a\b\..\c", r"C:\a\c")]
+    #[case(r"C:\a\.\b", r"C:\a\b")]
+    #[case(r"C:\..\..\a", r"C:\a")]
+    #[case(r"C:\a\b\..", r"C:\a")]
+    // trailing periods and spaces
+    #[case(r"C:\a.\b", r"C:\a\b")]
+    #[case(r"C:\a\b.", r"C:\a\b")]
+    #[case(r"C:\a\b. . ", r"C:\a\b")]
+    #[case(r"C:\a\b  ", r"C:\a\b")]
+    #[case(r"C:\a\b \", r"C:\a\b ")]
+    #[case(r"C:\a\...\b", r"C:\a\...\b")]
+    #[case(r"C:\a\b..\c", r"C:\a\b..\c")]
+    // unc roots
+    #[case(r"\\server\share\..\x", r"\\server\share\x")]
+    #[case(r"\\server\share\a\..\..\x", r"\\server\share\x")]
+    #[case(r"//server/share/a/./b", r"\\server\share\a\b")]
+    // device paths
+    #[case(r"\\.\C:\a\..\b", r"\\.\C:\b")]
+    #[case(r"\\.\C:\..\..\b", r"\\.\C:\b")]
+    #[case(r"\\.\pipe\..\x", r"\\.\pipe\x")]
+    #[case(r"\\.\nul", r"\\.\nul")]
+    // extended-length paths are left alone
+    #[case(r"\\?\C:\a\..\b.", r"\\?\C:\a\..\b.")]
+    #[case(r"\\?\UNC\server\share\.\x", r"\\?\UNC\server\share\.\x")]
+    // legacy dos devices
+    #[case(r"NUL", r"\\.\NUL")]
+    #[case(r"nul.txt", r"\\.\nul")]
+    #[case(r"C:\foo\COM1", r"\\.\COM1")]
+    #[case(r"C:\foo\lpt9.log", r"\\.\lpt9")]
+    #[case(r"C:\foo\con .txt", r"\\.\con")]
+    #[case(r"D:aux", r"\\.\aux")]
+    #[case(r"C:\foo\nul\x", r"C:\foo\nul\x")]
+    #[case(r"C:\foo\nul\", r"C:\foo\nul")]
+    #[case(r"C:\foo\COM10", r"C:\foo\COM10")]
+    #[case(r"C:\foo\nulx", r"C:\foo\nulx")]
+    #[case(r"\\server\share\nul", r"\\server\share\nul")]
+    fn full_path_name(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let state = state();
+
+        // act
+        let result = super::full_path_name(path, &state).unwrap();
+
+        // assert
+        assert_eq!(
+            result.builder().with_separator('\\').build_string(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(r"..\foo", r"C:\Users\foo")]
+    #[case(r"C:\a\b.", r"C:\a\b")]
+    #[case(r"C:\a\b \", r"C:\a\b ")]
+    #[case(r"C:\foo\nul.txt", r"\\.\nul")]
+    fn win32_full_path(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let state = state();
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.win32_full_path(&state).unwrap();
+
+        // assert
+        assert_eq!(
+            result.builder().with_separator('\\').build_string(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(r"foo")]
+    #[case(r"\foo")]
+    #[case(r"C:foo")]
+    #[case(r"\\server\share")]
+    #[case(r"\\?\C:\foo")]
+    fn new_should_fail(#[case] cwd: &str) {
+        // arrange
+        let cwd = Path::from_str(cwd).unwrap();
+
+        // act
+        let state = WindowsCwdState::new(&cwd);
+
+        // assert
+        assert!(state.is_err());
+    }
+}
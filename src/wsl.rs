@@ -0,0 +1,248 @@
+use alloc::string::{String, ToString};
+
+use crate::{
+    packed_list::PathSegmentList,
+    path::{Drive, Path, Prefix, Root},
+    windows::{drive_mount_path, mounted_drive_path},
+};
+
+const WSL_HOSTS: [&str; 2] = ["wsl.localhost", "wsl$"];
+
+/// How a WSL distribution exposes itself, used to translate paths across the
+/// boundary the way `wslpath` does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WslConfig {
+    mount_root: Path,
+    distro: String,
+}
+
+impl WslConfig {
+    /// Creates a config for `distro` with windows drives mounted under `/mnt`,
+    /// which is the default automount root.
+    pub fn new(distro: impl Into<String>) -> Self {
+        WslConfig {
+            mount_root: Path {
+                root: Some(Root::Normal),
+                segments: PathSegmentList::from("mnt".to_string()),
+                is_dir: true,
+                ..Path::new()
+            },
+            distro: distro.into(),
+        }
+    }
+
+    /// Sets the directory windows drives are mounted under, as configured by
+    /// `automount.root` in `wsl.conf`.
+    pub fn with_mount_root(mut self, mount_root: impl AsRef<Path>) -> Result<Self, &'static str> {
+        let mount_root = mount_root.as_ref();
+
+        if !mount_root.is_absolute() || !mount_root.is_unix_compatible() {
+            return Err("the mount root must be an absolute unix path");
+        }
+
+        self.mount_root = mount_root.clone();
+        self.mount_root.is_dir = true;
+        Ok(self)
+    }
+
+    pub fn mount_root(&self) -> &Path {
+        &self.mount_root
+    }
+
+    pub fn distro(&self) -> &str {
+        &self.distro
+    }
+}
+
+impl Path {
+    /// Translates a path inside WSL into the path windows sees.
+    ///
+    /// `/mnt/c/Users` becomes `C:\Users`, and any other absolute path is reached
+    /// through the distribution's share, as in `\\wsl.localhost\Ubuntu\home`.
+    /// Relative paths are the same on both sides and are returned unchanged.
+    pub fn wsl_to_windows(&self, config: &WslConfig) -> Result<Self, &'static str> {
+        if self.prefix.is_some() || self.drive.is_some() || self.root == Some(Root::Unc) {
+            return Err("only unix paths can be translated to windows paths");
+        }
+
+        if self.is_relative() {
+            return Ok(self.clone());
+        }
+
+        if let Some(path) = mounted_drive_path(self, &config.mount_root) {
+            return Ok(path);
+        }
+
+        let mut segments = PathSegmentList::new();
+        segments.push(WSL_HOSTS[0].to_string());
+        segments.push(config.distro.clone());
+
+        for segment in self.segments.iter() {
+            segments.push(segment.clone());
+        }
+
+        Ok(Path {
+            root: Some(Root::Unc),
+            segments,
+            stream: self.stream.clone(),
+            is_dir: self.is_dir(),
+            ..Path::new()
+        })
+    }
+
+    /// Translates a windows path into the path WSL sees.
+    ///
+    /// `C:\Users` becomes `/mnt/c/Users`, and `\\wsl.localhost\Ubuntu\home` or
+    /// `\\wsl$\Ubuntu\home` becomes `/home` when `Ubuntu` is the configured
+    /// distribution. Relative paths are the same on both sides and are returned
+    /// unchanged.
+    pub fn windows_to_wsl(&self, config: &WslConfig) -> Result<Self, &'static str> {
+        if self.prefix == Some(Prefix::Device) {
+            return Err("device paths have no WSL equivalent");
+        }
+
+        match (&self.drive, &self.root) {
+            (Some(Drive { letter }), Some(Root::Normal)) => {
+                Ok(drive_mount_path(self, *letter, &config.mount_root))
+            }
+            (None, Some(Root::Unc)) => {
+                let mut segments = self.segments.iter();
+                let host = segments.next().ok_or("unc paths must have a server")?;
+
+                if !WSL_HOSTS.iter().any(|h| h.eq_ignore_ascii_case(&host.0)) {
+                    return Err("network paths have no WSL equivalent");
+                }
+
+                let distro = segments
+                    .next()
+                    .ok_or("WSL paths must name a distribution")?;
+
+                if !distro.0.eq_ignore_ascii_case(&config.distro) {
+                    return Err("the path belongs to a different WSL distribution");
+                }
+
+                Ok(Path {
+                    root: Some(Root::Normal),
+                    segments: segments.cloned().collect(),
+                    stream: self.stream.clone(),
+                    is_dir: self.is_dir(),
+                    ..Path::new()
+                })
+            }
+            (Some(_), None) | (None, Some(Root::Normal)) => {
+                Err("drive-relative and rooted windows paths must be made absolute first")
+            }
+            (Some(_), Some(Root::Unc)) => Err("unc paths cannot have a drive"),
+            (None, None) => Ok(self.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("/mnt/c/Users/me", r"C:\Users\me")]
+    #[case("/mnt/d/src/app/", r"D:\src\app\")]
+    #[case("/mnt/c", r"C:\")]
+    #[case("/home/me/.bashrc", r"\\wsl.localhost\Ubuntu\home\me\.bashrc")]
+    #[case("/mnt", r"\\wsl.localhost\Ubuntu\mnt")]
+    #[case("/mnt/wsl/x", r"\\wsl.localhost\Ubuntu\mnt\wsl\x")]
+    #[case("/", r"\\wsl.localhost\Ubuntu\")]
+    #[case("src/main.rs", "src/main.rs")]
+    fn wsl_to_windows(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let config = WslConfig::new("Ubuntu");
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.wsl_to_windows(&config).unwrap();
+
+        // assert
+        assert_eq!(result, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case(r"C:\Users\me", "/mnt/c/Users/me")]
+    #[case(r"D:\src\app\", "/mnt/d/src/app/")]
+    #[case(r"C:\", "/mnt/c/")]
+    #[case(r"\\?\C:\x", "/mnt/c/x")]
+    #[case(r"\\wsl.localhost\Ubuntu\home\me", "/home/me")]
+    #[case(r"\\wsl$\ubuntu\home\me", "/home/me")]
+    #[case(r"\\?\UNC\wsl.localhost\Ubuntu\etc", "/etc")]
+    #[case(r"\\wsl.localhost\Ubuntu", "/")]
+    #[case("src/main.rs", "src/main.rs")]
+    fn windows_to_wsl(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let config = WslConfig::new("Ubuntu");
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.windows_to_wsl(&config).unwrap();
+
+        // assert
+        assert_eq!(result, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case(r"C:foo")]
+    #[case(r"\foo")]
+    #[case(r"\\server\share\x")]
+    #[case(r"\\wsl.localhost\Debian\home")]
+    #[case(r"\\wsl.localhost")]
+    #[case(r"\\.\COM1")]
+    fn windows_to_wsl_should_fail(#[case] path: &str) {
+        // arrange
+        let config = WslConfig::new("Ubuntu");
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.windows_to_wsl(&config);
+
+        // assert
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case("/c/Users", r"C:\Users")]
+    #[case("/home", r"\\wsl.localhost\Arch\home")]
+    fn wsl_to_windows_with_custom_mount_root(#[case] path: &str, #[case] expected: &str) {
+        // arrange
+        let config = WslConfig::new("Arch")
+            .with_mount_root(Path::from_str("/").unwrap())
+            .unwrap();
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let result = path.wsl_to_windows(&config).unwrap();
+
+        // assert
+        assert_eq!(result, Path::from_str(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case("/mnt/c/Users/me/file.txt")]
+    #[case("/mnt/z/")]
+    #[case("/home/me/file.txt")]
+    #[case("/etc/")]
+    fn round_trip(#[case] path: &str) {
+        // arrange
+        let config = WslConfig::new("Ubuntu");
+        let path = Path::from_str(path).unwrap();
+
+        // act
+        let round_tripped = path
+            .wsl_to_windows(&config)
+            .unwrap()
+            .windows_to_wsl(&config)
+            .unwrap();
+
+        // assert
+        assert_eq!(round_tripped, path);
+    }
+}